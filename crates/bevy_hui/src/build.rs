@@ -1,23 +1,25 @@
 use crate::{
     animation::{ActiveAnimation, AnimationDirection},
-    compile::CompileContextEvent,
-    data::{AttrTokens, HtmlTemplate, NodeType, XNode},
+    compile::{compile_content, eval_condition, template_name, CompileContextEvent},
+    data::{AttrTokens, HtmlTemplate, NodeType, Repeat, XNode},
+    media::UiMedia,
+    expr::{parse_segments, Segment},
     prelude::ComponentBindings,
-    styles::{HoverTimer, HtmlStyle, PressedTimer},
+    styles::{HtmlStyle, NodeStyles, UiClassList},
+    stylesheet::UiSelector,
+    theme::HuiTheme,
     util::SlotId,
+    value::PropertyValue,
 };
 use bevy::{platform::collections::HashMap, prelude::*};
-use nom::{
-    bytes::complete::{is_not, tag, take_until},
-    character::complete::multispace0,
-    sequence::{delimited, preceded, tuple},
-};
 use std::time::Duration;
 
 pub struct BuildPlugin;
 impl Plugin for BuildPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, (hotreload, spawn_ui, move_children_to_slot).chain())
+            .add_observer(despawn_loop_scopes)
+            .add_observer(despawn_slot_scopes)
             .register_type::<TemplatePropertySubscriber>()
             .register_type::<TemplateExpresions>()
             .register_type::<TemplateProperties>()
@@ -31,8 +33,12 @@ impl Plugin for BuildPlugin {
             .register_type::<UiTarget>()
             .register_type::<UiId>()
             .register_type::<SlotPlaceholder>()
+            .register_type::<SlotName>()
+            .register_type::<SlotScope>()
             .register_type::<UnslotedChildren>()
             .register_type::<HtmlNode>()
+            .register_type::<UiCondition>()
+            .register_type::<UiLoop>()
             .register_type::<super::data::XNode>()
             .register_type::<super::data::HtmlTemplate>()
             .register_type::<super::data::StyleAttr>()
@@ -44,27 +50,57 @@ impl Plugin for BuildPlugin {
 /// which owns the template properties
 #[derive(Component, Clone, Deref, Debug, DerefMut, Copy, Reflect)]
 #[reflect]
-pub struct TemplateScope(Entity);
+pub struct TemplateScope(pub(crate) Entity);
 
 /// The property definition of a template,
 /// this component can be found on the template root
 /// entity, use `TemplateScope` (exists on all nodes, part of a template)
 /// to get access to the root.
-#[derive(Component, Debug, Clone, Default, Reflect, Deref, DerefMut)]
+#[derive(Component, Debug, Clone, Default, PartialEq, Reflect, Deref, DerefMut)]
 #[reflect]
-pub struct TemplateProperties(pub HashMap<String, String>);
+pub struct TemplateProperties(pub HashMap<String, PropertyValue>);
 
 impl TemplateProperties {
     pub fn with(mut self, key: &str, value: &str) -> Self {
-        self.insert(key.to_string(), value.to_string());
+        self.insert(key.to_string(), value.into());
         self
     }
     pub fn set(&mut self, key: &str, value: &str) -> &mut Self {
-        self.insert(key.to_string(), value.to_string());
+        self.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// `with` for a typed value, like `Color` or `Val`
+    pub fn with_typed(mut self, key: &str, value: impl Into<PropertyValue>) -> Self {
+        self.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// sets a typed value, it is used as it is,
+    /// without a round trip through the parser.
+    pub fn set_typed(&mut self, key: &str, value: impl Into<PropertyValue>) -> &mut Self {
+        self.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// adds string properties, like `<property>` defaults.
+    pub(crate) fn extend_str(&mut self, defs: &HashMap<String, String>) -> &mut Self {
+        self.extend(
+            defs.iter()
+                .map(|(key, value)| (key.clone(), value.as_str().into())),
+        );
         self
     }
 }
 
+impl From<&HashMap<String, String>> for TemplateProperties {
+    fn from(defs: &HashMap<String, String>) -> Self {
+        let mut props = TemplateProperties::default();
+        props.extend_str(defs);
+        props
+    }
+}
+
 /// Entites that need to be notified, when the
 /// template properties change.
 #[derive(Component, Clone, Default, Debug, Deref, DerefMut, Reflect)]
@@ -73,21 +109,44 @@ pub struct TemplatePropertySubscriber(pub Vec<Entity>);
 
 #[derive(Component)]
 pub struct InsideSlot {
-    owner: Entity,
+    pub(crate) owner: Entity,
 }
 
 #[derive(Component, Reflect, Debug)]
 #[reflect]
 pub struct SlotPlaceholder {
     owner: Entity,
+    name: Option<String>,
+    scope: Option<Entity>,
+}
+
+/// the named slot of the parent component,
+/// the node is moved to. `slot="header"`
+#[derive(Component, Reflect, Debug, Deref)]
+#[reflect]
+pub struct SlotName(pub(crate) String);
+
+/// The scope of content passed into a `<slot row="{current}"/>`.
+/// Holds the slot properties on top of the callers properties,
+/// slotted nodes use it as their `TemplateScope`.
+#[derive(Component, Reflect, Debug)]
+#[reflect]
+pub struct SlotScope {
+    pub(crate) owner: Entity,
+    pub(crate) defs: HashMap<String, String>,
 }
 
+/// the slot scopes of a component, they
+/// are not part of the ui tree.
+#[derive(Component, Default, Debug, Deref, DerefMut)]
+pub struct SlotScopes(Vec<Entity>);
+
 /// ref to unresolved nodes that
 /// need to move to the `<slot/>`
 /// when the template is loaded.
 #[derive(Component, Reflect, Debug)]
 #[reflect]
-pub struct UnslotedChildren(Entity);
+pub struct UnslotedChildren(pub(crate) Entity);
 
 /// entities subscribed to the owners interaction
 /// component
@@ -98,7 +157,7 @@ pub struct InteractionObverser(Vec<Entity>);
 /// to a solid attribute
 #[derive(Component, Reflect, Deref, Debug, DerefMut)]
 #[reflect]
-pub struct TemplateExpresions(Vec<AttrTokens>);
+pub struct TemplateExpresions(pub(crate) Vec<AttrTokens>);
 
 /// Any attribute prefixed with `tag:my_tag="my_value"`
 /// will be availble here.
@@ -113,8 +172,11 @@ impl Tags {
 }
 
 /// holds ref to the raw uncompiled text content
-#[derive(Component, Deref, DerefMut)]
-pub struct ContentId(SlotId);
+#[derive(Component)]
+pub struct ContentId {
+    pub(crate) template: AssetId<HtmlTemplate>,
+    pub(crate) content: SlotId,
+}
 
 /// the entities owned uid hashed as u64
 #[derive(Component, Debug, Default, Hash, Deref, DerefMut, Reflect)]
@@ -140,6 +202,41 @@ pub struct UiWatch(pub Entity);
 #[derive(Component, Default)]
 pub struct FullyBuild;
 
+/// A node with an `if="{show}"` attribute. The subtree
+/// is only build while the condition evaluates to true.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect]
+pub struct UiCondition {
+    pub template: AssetId<HtmlTemplate>,
+    pub node: u64,
+    pub expression: String,
+    pub visible: bool,
+}
+
+/// A node with a `for="item in {items}"` attribute.
+/// Holds the scope and nodes of every build list element.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect]
+pub struct UiLoop {
+    pub template: AssetId<HtmlTemplate>,
+    pub node: u64,
+    pub scope: Entity,
+    pub repeat: Repeat,
+    pub iterations: Vec<LoopIteration>,
+}
+
+/// a single list element. The scope entity owns
+/// the `TemplateProperties` with `item` and `index`,
+/// a full copy of the outer scope.
+#[derive(Debug, Clone, Reflect)]
+#[reflect]
+pub struct LoopIteration {
+    pub scope: Entity,
+    pub nodes: Vec<Entity>,
+    /// the item it was compiled with
+    pub item: PropertyValue,
+}
+
 /// Eventlistener interaction transition to Hover
 #[derive(Component, Debug, Deref, DerefMut, Reflect)]
 #[reflect]
@@ -208,6 +305,34 @@ fn hotreload(
     });
 }
 
+/// iteration scopes are not part of the ui tree,
+/// clean them up with their loop.
+fn despawn_loop_scopes(trigger: On<Remove, UiLoop>, mut cmd: Commands, loops: Query<&UiLoop>) {
+    let Ok(ui_loop) = loops.get(trigger.entity) else {
+        return;
+    };
+
+    ui_loop.iterations.iter().for_each(|iteration| {
+        cmd.entity(iteration.scope).try_despawn();
+    });
+}
+
+/// slot scopes are not part of the ui tree,
+/// clean them up with their component.
+fn despawn_slot_scopes(
+    trigger: On<Remove, SlotScopes>,
+    mut cmd: Commands,
+    slot_scopes: Query<&SlotScopes>,
+) {
+    let Ok(scopes) = slot_scopes.get(trigger.entity) else {
+        return;
+    };
+
+    scopes.iter().for_each(|scope| {
+        cmd.entity(*scope).try_despawn();
+    });
+}
+
 #[derive(Bundle)]
 struct KeepComps {
     pub parent: ChildOf,
@@ -219,50 +344,162 @@ struct KeepComps {
     pub scope: TemplateScope,
 }
 
-fn move_children_to_slot(
+/// nodes that subscribe to their scope
+type CompiledNode = Or<(
+    With<TemplateExpresions>,
+    With<ContentId>,
+    With<UiCondition>,
+    With<UiLoop>,
+)>;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn move_children_to_slot(
     mut cmd: Commands,
-    unsloted_includes: Query<(Entity, &UnslotedChildren)>,
+    unsloted_includes: Query<&UnslotedChildren>,
     children: Query<&Children>,
-    slots: Query<(Entity, &SlotPlaceholder)>,
-    parent: Query<&ChildOf>,
+    slots: Query<(Entity, &SlotPlaceholder, &ChildOf)>,
+    slot_names: Query<&SlotName>,
+    scopes: Query<&TemplateScope>,
+    mut subscriber: Query<&mut TemplatePropertySubscriber>,
+    mut loops: Query<&mut UiLoop>,
+    kinds: Query<(), CompiledNode>,
+    properties: Query<(), With<TemplateProperties>>,
+    html_nodes: Query<&HtmlNode>,
+    templates: Res<Assets<HtmlTemplate>>,
+    server: Res<AssetServer>,
 ) {
-    unsloted_includes
-        .iter()
-        .for_each(|(entity, UnslotedChildren(slot_holder))| {
-            let Some(placeholder_entity) = slots
-                .iter()
-                .find_map(|(slot_ent, slot)| (slot.owner == entity).then_some(slot_ent))
-            else {
-                return;
-            };
+    let mut owners: HashMap<Entity, Vec<(Entity, &SlotPlaceholder, Entity)>> = HashMap::default();
+    slots.iter().for_each(|(slot_entity, slot, parent)| {
+        owners
+            .entry(slot.owner)
+            .or_default()
+            .push((slot_entity, slot, parent.parent()));
+    });
 
-            let Ok(slot_parent) = parent.get(placeholder_entity).map(|p| p.parent()) else {
-                error!("parentless slot, impossible");
-                return;
-            };
+    owners.into_iter().for_each(|(owner, mut owned_slots)| {
+        let slot_holder = unsloted_includes.get(owner).ok().map(|holder| holder.0);
+        let sloted = slot_holder
+            .and_then(|holder| children.get(holder).ok())
+            .map(|children| children.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        // later slots first, so inserting
+        // does not shift the earlier ones
+        let position = |slot_entity: Entity, parent: Entity| {
+            children
+                .get(parent)
+                .ok()
+                .and_then(|siblings| siblings.iter().position(|sib| sib == slot_entity))
+                .unwrap_or_default()
+        };
+        owned_slots.sort_by_key(|(slot_entity, _, parent)| {
+            std::cmp::Reverse(position(*slot_entity, *parent))
+        });
 
-            _ = children.get(*slot_holder).map(|children| {
-                children.iter().for_each(|child| {
-                    if child != slot_parent {
-                        cmd.entity(child).insert(InsideSlot { owner: entity });
-                        cmd.entity(slot_parent).add_child(child);
-                    }
+        let mut placed = vec![];
+        for (slot_entity, slot, slot_parent) in owned_slots {
+            let index = position(slot_entity, slot_parent);
+            let content = sloted
+                .iter()
+                .filter(|child| **child != slot_parent)
+                .filter(|child| {
+                    slot_names.get(**child).ok().map(|name| name.as_str()) == slot.name.as_deref()
                 })
-            });
+                .copied()
+                .collect::<Vec<_>>();
+
+            if content.is_empty() {
+                // default content, written inside the `<slot>`
+                let fallback = children
+                    .get(slot_entity)
+                    .map(|children| children.iter().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                cmd.entity(slot_parent).insert_children(index, &fallback);
+            } else {
+                content.iter().for_each(|child| {
+                    cmd.entity(*child).insert(InsideSlot { owner });
+                });
+                cmd.entity(slot_parent).insert_children(index, &content);
+
+                // --------------------
+                // scoped slot, the content resolves the
+                // slot properties before the callers
+                if let Some(slot_scope) = slot.scope {
+                    let caller = scopes.get(owner).ok().map(|scope| **scope);
+                    let retarget = content
+                        .iter()
+                        .flat_map(|child| {
+                            std::iter::once(*child).chain(children.iter_descendants(*child))
+                        })
+                        .filter(|desc| {
+                            // the scope of a reloaded slot is already gone
+                            scopes.get(*desc).is_ok_and(|scope| {
+                                Some(**scope) == caller || properties.get(**scope).is_err()
+                            })
+                        })
+                        .collect::<Vec<_>>();
+
+                    retarget.iter().for_each(|desc| {
+                        cmd.entity(*desc).insert(TemplateScope(slot_scope));
+                        _ = loops.get_mut(*desc).map(|mut ui_loop| {
+                            ui_loop.scope = slot_scope;
+                        });
+                    });
+
+                    let subs = retarget
+                        .iter()
+                        .filter(|desc| kinds.contains(**desc))
+                        .copied()
+                        .collect::<Vec<_>>();
+
+                    let caller_subs = caller.and_then(|caller| subscriber.get_mut(caller).ok());
+                    if let Some(mut caller_subs) = caller_subs {
+                        caller_subs.retain(|sub| !subs.contains(sub));
+                        caller_subs.push(slot_scope);
+                    }
 
-            cmd.entity(entity).remove::<UnslotedChildren>();
-            cmd.entity(placeholder_entity).despawn();
-            cmd.entity(*slot_holder).despawn();
-        });
+                    cmd.entity(slot_scope)
+                        .insert(TemplatePropertySubscriber(subs));
+                    cmd.trigger(CompileContextEvent { entity: slot_scope });
+                }
+                placed.extend(content);
+            }
+
+            cmd.entity(slot_entity).despawn();
+        }
+
+        if let Some(holder) = slot_holder {
+            sloted
+                .iter()
+                .filter(|child| !placed.contains(*child))
+                .filter_map(|child| slot_names.get(*child).ok())
+                .for_each(|name| warn!("component {owner} has no slot named `{}`", **name));
+
+            let dropped = sloted
+                .iter()
+                .filter(|child| !placed.contains(*child) && !slot_names.contains(**child))
+                .collect::<Vec<_>>();
+            if !dropped.is_empty() {
+                let name = html_nodes
+                    .get(owner)
+                    .map(|node| template_name(&node.0, &templates, &server))
+                    .unwrap_or_else(|_| "component".to_string());
+                warn!("`{name}` {owner} has no unnamed slot, dropped the children {dropped:?}");
+            }
+            cmd.entity(owner).remove::<UnslotedChildren>();
+            cmd.entity(holder).despawn();
+        }
+    });
 }
 
-fn spawn_ui(
+pub(crate) fn spawn_ui(
     mut cmd: Commands,
     mut unbuild: Query<(Entity, &HtmlNode, &mut TemplateProperties), Without<FullyBuild>>,
     assets: Res<Assets<HtmlTemplate>>,
     server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     custom_comps: Res<ComponentBindings>,
+    theme: Res<HuiTheme>,
 ) {
     unbuild
         .iter_mut()
@@ -271,32 +508,45 @@ fn spawn_ui(
                 return;
             };
 
-            template.properties.iter().for_each(|(key, val)| {
-                _ = state.try_insert(key.to_owned(), val.clone());
+            // compiled below, not a change
+            let state = state.bypass_change_detection();
+            template.properties.iter().for_each(|(key, def)| {
+                if let Some(default) = &def.default {
+                    _ = state.try_insert(key.to_owned(), default.as_str().into());
+                }
             });
+            theme.apply_to(state);
 
             let mut builder = TemplateBuilder::new(
                 root_entity,
+                handle.id(),
                 cmd.reborrow(),
                 &server,
                 &mut texture_atlases,
                 &custom_comps,
                 &template,
+                state,
             );
 
-            if let Some(node) = template.root.first() {
-                builder.build_tree(node);
-                builder.finalize_relations();
-                cmd.trigger(CompileContextEvent {
-                    entity: root_entity,
-                });
-            } else {
-                warn!("template has no root node!");
+            match template.root.as_slice() {
+                [] => {
+                    warn!("template has no root node!");
+                    return;
+                }
+                // a single root is build into the `HtmlNode` entity
+                [node] if !matches!(node.node_type, NodeType::Fragment | NodeType::Block) => {
+                    builder.build_tree(node);
+                }
+                // multiple roots are build as siblings below it
+                roots => {
+                    builder.build_children(root_entity, roots);
+                }
             }
 
-            if template.root.len() > 1 {
-                warn!("templates currently only support one root node, ignoring the rest");
-            }
+            builder.finalize_relations();
+            cmd.trigger(CompileContextEvent {
+                entity: root_entity,
+            });
         });
 }
 
@@ -348,11 +598,13 @@ fn build_animation(style: &HtmlStyle) -> Option<ActiveAnimation> {
     })
 }
 
-struct TemplateBuilder<'w, 's> {
+pub(crate) struct TemplateBuilder<'w, 's> {
     cmd: Commands<'w, 's>,
     server: &'w AssetServer,
     texture_atlases: &'w mut Assets<TextureAtlasLayout>,
     scope: Entity,
+    template_id: AssetId<HtmlTemplate>,
+    properties: &'w TemplateProperties,
     comps: &'w ComponentBindings,
     subscriber: TemplatePropertySubscriber,
     ids: HashMap<String, Entity>,
@@ -362,17 +614,22 @@ struct TemplateBuilder<'w, 's> {
 }
 
 impl<'w, 's> TemplateBuilder<'w, 's> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         scope: Entity,
+        template_id: AssetId<HtmlTemplate>,
         cmd: Commands<'w, 's>,
         server: &'w AssetServer,
         texture_atlases: &'w mut Assets<TextureAtlasLayout>,
         comps: &'w ComponentBindings,
         template: &'w HtmlTemplate,
+        properties: &'w TemplateProperties,
     ) -> Self {
         Self {
             cmd,
             scope,
+            template_id,
+            properties,
             server,
             texture_atlases,
             comps,
@@ -409,54 +666,87 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
                 None => warn!("undefined watch target `{target_str}`"),
             });
 
+        // append, conditional subtrees are build
+        // into an already existing scope.
+        let subscriber = std::mem::take(&mut self.subscriber);
         self.cmd
             .entity(self.scope)
-            .insert((std::mem::take(&mut self.subscriber), FullyBuild));
+            .insert(FullyBuild)
+            .entry::<TemplatePropertySubscriber>()
+            .or_default()
+            .and_modify(move |mut subs| {
+                subscriber.iter().for_each(|sub| {
+                    if !subs.contains(sub) {
+                        subs.push(*sub);
+                    }
+                });
+            });
     }
 
     pub fn build_tree(&mut self, root: &XNode) {
         self.build_node(self.scope, root);
     }
 
-    fn build_node(&mut self, entity: Entity, node: &XNode) {
-        let styles = HtmlStyle::from(node.styles.clone());
-        // ----------------------
-        // timers
-        self.cmd
-            .entity(entity)
-            .insert(PressedTimer::new(Duration::from_secs_f32(
-                styles.computed.delay.max(0.01),
-            )))
-            .insert(HoverTimer::new(Duration::from_secs_f32(
-                styles.computed.delay.max(0.01),
-            )));
-
-        // ---------------------
-        // shadow
-        if let Some(shadow) = styles.computed.shadow.as_ref() {
-            self.cmd.entity(entity).insert(shadow.clone());
+    pub fn build_node(&mut self, entity: Entity, node: &XNode) {
+        if let Some(condition) = &node.condition {
+            if entity == self.scope {
+                warn!("`if` is not supported on the template root node, ignoring it");
+            } else {
+                // errors are logged, once the node is compiled
+                let visible = eval_condition(condition, self.properties).unwrap_or_default();
+                self.cmd.entity(entity).insert(UiCondition {
+                    template: self.template_id,
+                    node: node.uuid,
+                    expression: condition.clone(),
+                    visible,
+                });
+                self.subscriber.push(entity);
+
+                if !visible {
+                    self.cmd.entity(entity).insert((
+                        Node {
+                            display: Display::None,
+                            ..default()
+                        },
+                        TemplateScope(self.scope),
+                    ));
+                    return;
+                }
+            }
         }
 
-        if let Some(shadow) = styles.computed.text_shadow.as_ref() {
-            self.cmd.entity(entity).insert(shadow.clone());
-        }
+        self.spawn_node(entity, node);
+    }
 
-        // ---------------------
-        // text_layout
-        if let Some(text_layout) = styles.computed.text_layout.as_ref() {
-            self.cmd.entity(entity).insert(text_layout.clone());
-        }
+    fn spawn_node(&mut self, entity: Entity, node: &XNode) {
+        let styles = HtmlStyle::from(self.template.node_styles(node));
+        // ----------------------
+        // timers, shadows, outline ..
+        styles.insert_components(self.cmd.entity(entity));
+
+        // ----------------------
+        // stylesheet selector, classes
+        self.cmd.entity(entity).insert((
+            UiSelector {
+                node_type: node.node_type.name().to_string(),
+                id: node.id.clone(),
+            },
+            UiClassList::new(self.template_id, node.classes.clone()),
+            UiMedia::default(),
+            NodeStyles(node.styles.clone()),
+        ));
 
         if entity != self.scope {
             self.cmd.entity(entity).insert(TemplateScope(self.scope));
         }
 
         // ----------------------
-        //register prop listner
-        if node.uncompiled.len() > 0 {
-            self.cmd.entity(entity).insert(TemplateExpresions(
-                node.uncompiled.iter().cloned().collect(),
-            ));
+        //register prop listner, slots
+        // pass them to their scope
+        if !node.uncompiled.is_empty() && !matches!(node.node_type, NodeType::Slot) {
+            self.cmd
+                .entity(entity)
+                .insert(TemplateExpresions(node.uncompiled.to_vec()));
             self.subscriber.push(entity);
         }
 
@@ -473,6 +763,10 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
             self.targets.insert(entity, target.clone());
         }
 
+        if let Some(slot) = &node.slot {
+            self.cmd.entity(entity).insert(SlotName(slot.clone()));
+        }
+
         if let Some(watch) = &node.watch {
             match self.watch.get_mut(watch) {
                 Some(list) => {
@@ -490,23 +784,11 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
             listener.clone().self_insert(self.cmd.entity(entity));
         });
 
-        // ----------------------
-        // dirty outline
-        if let Some(outline) = styles.computed.outline.as_ref() {
-            self.cmd.entity(entity).insert(outline.clone());
-        }
-
-        // ----------------------
-        // pickable
-        #[cfg(feature = "picking")]
-        if let Some(pickable) = styles.computed.pickable.as_ref() {
-            self.cmd.entity(entity).insert(pickable.clone());
-        }
-
         match &node.node_type {
             // --------------------------------
-            // div node
-            NodeType::Node => {
+            // div node, fragments and blocks are flattened by
+            // `build_children` and never end up here
+            NodeType::Node | NodeType::Fragment | NodeType::Block => {
                 self.cmd.entity(entity).insert((Node::default(), styles));
             }
             // --------------------------------
@@ -559,21 +841,16 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
                 ));
             }
             // --------------------------------
-            // spawn image
+            // spawn text
             NodeType::Text => {
-                let content = self
-                    .template
-                    .content
-                    .get(node.content_id)
-                    .map(|t| t.trim().to_string())
-                    .unwrap_or_default();
-
-                if is_templated(&content) {
-                    self.cmd.entity(entity).insert(ContentId(node.content_id));
-                    self.subscriber.push(entity);
-                }
-
-                self.cmd.entity(entity).insert((Text(content), styles));
+                let text = self.build_content(entity, node);
+                self.cmd.entity(entity).insert((Text(text), styles));
+            }
+            // --------------------------------
+            // spawn text span
+            NodeType::Span => {
+                let text = self.build_content(entity, node);
+                self.cmd.entity(entity).insert((TextSpan(text), styles));
             }
             // --------------------------------
             // spawn button
@@ -584,12 +861,14 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
                 // mark children
                 self.comps.try_spawn(custom, entity, &mut self.cmd);
                 if node.children.len() > 0 {
-                    let slot_holder = self.cmd.spawn(Node::default()).id();
-                    for child_node in node.children.iter() {
-                        let child_entity = self.cmd.spawn_empty().id();
-                        self.build_node(child_entity, child_node);
-                        self.cmd.entity(slot_holder).add_child(child_entity);
-                    }
+                    let slot_holder = self
+                        .cmd
+                        .spawn(Node {
+                            display: Display::None,
+                            ..default()
+                        })
+                        .id();
+                    self.build_children(slot_holder, &node.children);
 
                     self.cmd
                         .entity(entity)
@@ -598,20 +877,31 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
 
                 self.cmd
                     .entity(entity)
-                    .insert(TemplateProperties(node.defs.clone()));
+                    .insert(TemplateProperties::from(&node.defs));
 
                 if node.uncompiled.len() > 0 {
                     self.subscriber.push(entity);
                 }
 
+                if node.repeat.is_some() {
+                    warn!("`for` is not supported on custom components, ignoring it");
+                }
+
                 return;
             }
             // --------------------------------
             // spawn slot
             NodeType::Slot => {
-                self.cmd
-                    .entity(entity)
-                    .insert((Node::default(), SlotPlaceholder { owner: self.scope }));
+                let scope = (!node.defs.is_empty() || !node.uncompiled.is_empty())
+                    .then(|| self.build_slot_scope(node));
+                self.cmd.entity(entity).insert((
+                    Node::default(),
+                    SlotPlaceholder {
+                        owner: self.scope,
+                        name: node.name.clone(),
+                        scope,
+                    },
+                ));
             }
             // --------------------------------
             // don't render
@@ -620,20 +910,310 @@ impl<'w, 's> TemplateBuilder<'w, 's> {
             }
         };
 
-        for child in node.children.iter() {
+        // ----------------------
+        // loops build their children
+        // on compile, once per list element
+        if let Some(repeat) = &node.repeat {
+            self.cmd.entity(entity).insert(UiLoop {
+                template: self.template_id,
+                node: node.uuid,
+                scope: self.scope,
+                repeat: repeat.clone(),
+                iterations: vec![],
+            });
+            self.subscriber.push(entity);
+            return;
+        }
+
+        self.build_children(entity, &node.children);
+    }
+
+    /// spawns the scope of a slot with properties,
+    /// compiled from the component scope.
+    fn build_slot_scope(&mut self, node: &XNode) -> Entity {
+        let slot_scope = self
+            .cmd
+            .spawn((
+                SlotScope {
+                    owner: self.scope,
+                    defs: node.defs.clone(),
+                },
+                TemplateScope(self.scope),
+                TemplateProperties::from(&node.defs),
+                TemplateExpresions(node.uncompiled.clone()),
+            ))
+            .id();
+
+        self.cmd
+            .entity(self.scope)
+            .entry::<SlotScopes>()
+            .or_default()
+            .and_modify(move |mut scopes| scopes.push(slot_scope));
+        self.subscriber.push(slot_scope);
+        slot_scope
+    }
+
+    /// subscribes templated text content and
+    /// returns its first compiled value.
+    fn build_content(&mut self, entity: Entity, node: &XNode) -> String {
+        let content = self
+            .template
+            .content
+            .get(node.content_id)
+            .cloned()
+            .unwrap_or_default();
+
+        if is_templated(&content) {
+            self.cmd.entity(entity).insert(ContentId {
+                template: self.template_id,
+                content: node.content_id,
+            });
+            self.subscriber.push(entity);
+        }
+
+        // resolves escaped braces and gives expressions their
+        // first value, errors are logged once the text is compiled
+        compile_content(&content, self.properties, &mut vec![])
+    }
+
+    /// builds nodes as children of `parent`,
+    /// returns the spawned entities in order.
+    /// `<fragment>` and `<block>` nodes are replaced by their children.
+    pub fn build_children(&mut self, parent: Entity, nodes: &[XNode]) -> Vec<Entity> {
+        let mut entities = vec![];
+        for child in nodes.iter() {
+            if let NodeType::Fragment | NodeType::Block = child.node_type {
+                if child.condition.is_some() || child.repeat.is_some() {
+                    warn!("`if` and `for` are not supported on fragments and blocks, ignoring them");
+                }
+                entities.extend(self.build_children(parent, &child.children));
+                continue;
+            }
+
             let child_entity = self.cmd.spawn_empty().id();
             self.build_node(child_entity, child);
-            self.cmd.entity(entity).add_child(child_entity);
+            self.cmd.entity(parent).add_child(child_entity);
+            entities.push(child_entity);
         }
+        entities
     }
 }
 
-//@todo:dirty AF
+/// text with at least one `{...}` expression
 pub fn is_templated(input: &str) -> bool {
-    let parts: Result<(&str, (&str, &str)), nom::Err<nom::error::Error<&str>>> = tuple((
-        take_until("{"),
-        delimited(tag("{"), preceded(multispace0, is_not("}")), tag("}")),
-    ))(input);
+    parse_segments::<nom::error::Error<&[u8]>>(input.as_bytes())
+        .is_ok_and(|(_, segments)| {
+            segments
+                .iter()
+                .any(|segment| matches!(segment, Segment::Expression(_)))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        add_component, add_template, find, spawn_handle, spawn_template, test_app, text,
+    };
+
+    /// the `UiId`s of the children of a node
+    fn child_ids(app: &App, entity: Entity) -> Vec<String> {
+        app.world()
+            .get::<Children>(entity)
+            .map(|children| {
+                children
+                    .iter()
+                    .filter_map(|child| app.world().get::<UiId>(child))
+                    .map(|id| id.id().clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-    parts.is_ok()
+    #[test]
+    fn test_named_and_default_slots() {
+        let mut app = test_app();
+        add_component(
+            &mut app,
+            "card",
+            r#"
+            <template>
+                <node id="card">
+                    <slot name="header"><text id="untitled">Untitled</text></slot>
+                    <slot/>
+                    <slot name="footer"><text id="no_footer">none</text></slot>
+                </node>
+            </template>
+            "#,
+        );
+        spawn_template(
+            &mut app,
+            r#"
+            <template>
+                <node>
+                    <card>
+                        <text id="body">content</text>
+                        <text id="title" slot="header">Inventory</text>
+                    </card>
+                </node>
+            </template>
+            "#,
+            TemplateProperties::default(),
+        );
+        app.update();
+
+        let card = find(&mut app, "card")[0];
+        assert_eq!(child_ids(&app, card), vec!["title", "body", "no_footer"]);
+        assert!(find(&mut app, "untitled").is_empty());
+        assert!(app
+            .world_mut()
+            .query::<&UnslotedChildren>()
+            .iter(app.world())
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_drop_unslotted_children() {
+        let mut app = test_app();
+        add_component(
+            &mut app,
+            "card",
+            r#"
+            <template>
+                <node id="card">
+                    <slot name="header"/>
+                </node>
+            </template>
+            "#,
+        );
+        spawn_template(
+            &mut app,
+            r#"
+            <template>
+                <node>
+                    <card>
+                        <text id="title" slot="header">Inventory</text>
+                        <text id="body">content</text>
+                    </card>
+                </node>
+            </template>
+            "#,
+            TemplateProperties::default(),
+        );
+        app.update();
+
+        // without an unnamed slot, the children are despawned with their holder
+        let card = find(&mut app, "card")[0];
+        assert_eq!(child_ids(&app, card), vec!["title"]);
+        assert!(find(&mut app, "body").is_empty());
+        assert!(app
+            .world_mut()
+            .query::<&UnslotedChildren>()
+            .iter(app.world())
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_scoped_slot_properties() {
+        let mut app = test_app();
+        add_component(
+            &mut app,
+            "table",
+            r#"
+            <template>
+                <property name="current">first</property>
+                <node id="table">
+                    <slot row="{current}" index="0"/>
+                </node>
+            </template>
+            "#,
+        );
+        spawn_template(
+            &mut app,
+            r#"
+            <template>
+                <node>
+                    <table>
+                        <text id="cell">{row} in {index}, {title}</text>
+                    </table>
+                </node>
+            </template>
+            "#,
+            TemplateProperties::default().with("title", "caller"),
+        );
+        app.update();
+
+        let cell = find(&mut app, "cell")[0];
+        assert_eq!(text(&app, cell), "first in 0, caller");
+    }
+
+    #[test]
+    fn test_spawn_extended_template() {
+        let mut app = test_app();
+        let base = add_template(
+            &mut app,
+            r#"
+            <template>
+                <property name="title">base</property>
+                <node>
+                    <block name="title"><text id="title">{title}</text></block>
+                    <block name="body"><text id="body">default body</text></block>
+                </node>
+            </template>
+            "#,
+        );
+        let screen = add_template(
+            &mut app,
+            r#"
+            <template extends="base.html">
+                <property name="title">screen</property>
+                <style>
+                    .wide { width: 50px; }
+                </style>
+                <block name="body">
+                    <text id="screen_body" class="wide">{title} body</text>
+                </block>
+            </template>
+            "#,
+        );
+
+        let mut templates = app.world_mut().resource_mut::<Assets<HtmlTemplate>>();
+        let base = templates.remove(&base).unwrap();
+        let merged = templates.remove(&screen).unwrap().merge_into(base);
+        let handle = templates.add(merged);
+        spawn_handle(&mut app, handle, TemplateProperties::default());
+
+        let title = find(&mut app, "title")[0];
+        assert_eq!(text(&app, title), "screen");
+        assert!(find(&mut app, "body").is_empty());
+
+        let body = find(&mut app, "screen_body")[0];
+        assert_eq!(text(&app, body), "screen body");
+        assert_eq!(app.world().get::<Node>(body).unwrap().width, Val::Px(50.));
+    }
+
+    #[test]
+    fn test_spawn_multi_root() {
+        let mut app = test_app();
+        let root = spawn_template(
+            &mut app,
+            r#"
+            <template>
+                <node id="a"/>
+                <fragment>
+                    <node id="b"/>
+                    <text id="c">{title}</text>
+                </fragment>
+            </template>
+            "#,
+            TemplateProperties::default().with("title", "hello"),
+        );
+
+        assert_eq!(child_ids(&app, root), vec!["a", "b", "c"]);
+        let c = app.world().get::<Children>(root).unwrap()[2];
+        assert_eq!(text(&app, c), "hello");
+        assert_eq!(**app.world().get::<TemplateScope>(c).unwrap(), root);
+    }
 }
@@ -1,18 +1,20 @@
 use crate::{
     adaptor::AssetServerAdaptor,
     build::{
-        ContentId, HtmlNode, Tags, TemplateExpresions, TemplateProperties,
-        TemplatePropertySubscriber, TemplateScope,
+        ContentId, FullyBuild, HtmlNode, InsideSlot, LoopIteration, SlotScope, Tags,
+        TemplateBuilder, TemplateExpresions, TemplateProperties, TemplatePropertySubscriber,
+        TemplateScope, UiCondition, UiId, UiLoop, UnslotedChildren,
     },
-    data::HtmlTemplate,
-    styles::HtmlStyle,
-};
-use bevy::prelude::*;
-use nom::{
-    bytes::complete::{is_not, tag, take_until},
-    character::complete::multispace0,
-    sequence::{delimited, preceded, tuple},
+    data::{HtmlTemplate, Repeat},
+    error::{ExprError, PropertyError},
+    media::UiMedia,
+    prelude::ComponentBindings,
+    styles::{replace_style, update_node_style, DynamicStyles, HtmlStyle, StyleTransition},
+    theme::apply_theme,
+    value::PropertyValue,
 };
+use crate::expr::{parse_expression, parse_segments, Segment, Value};
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
 
 pub struct CompilePlugin;
 impl Plugin for CompilePlugin {
@@ -21,43 +23,101 @@ impl Plugin for CompilePlugin {
         // app.add_event::<CompileContextEvent>();
         // app.add_event::<CompileContentEvent>();
         app.add_observer(compile_node);
+        app.add_observer(compile_node_keys);
         app.add_observer(compile_context);
         app.add_observer(compile_text);
+        app.add_observer(compile_condition);
+        app.add_observer(compile_loop);
+        app.add_systems(
+            Update,
+            recompile_changed_properties
+                .after(apply_theme)
+                .before(update_node_style),
+        );
     }
 }
 
+/// recompiles templates and custom components after their
+/// `TemplateProperties` changed, once per frame for any number
+/// of changed keys. Spawning compiles on its own, the
+/// compile observers write properties without change detection.
+pub(crate) fn recompile_changed_properties(
+    mut cmd: Commands,
+    changed: Query<(Entity, Ref<TemplateProperties>), With<HtmlNode>>,
+) {
+    changed
+        .iter()
+        .filter(|(_, properties)| properties.is_changed() && !properties.is_added())
+        .for_each(|(entity, _)| cmd.trigger(CompileContextEvent { entity }));
+}
+
 #[derive(Event)]
 pub struct CompileContentEvent {
     pub entity: Entity,
 }
 
+type TextNode<'a> = (
+    &'a ContentId,
+    Option<&'a TemplateScope>,
+    Option<&'a mut Text>,
+    Option<&'a mut TextSpan>,
+);
+
 fn compile_text(
     trigger: On<CompileContentEvent>,
-    mut nodes: Query<(&ContentId, &TemplateScope, &mut Text)>,
-    root: Query<(&HtmlNode, &TemplateProperties)>,
+    mut nodes: Query<TextNode>,
+    contexts: Query<&TemplateProperties>,
     templates: Res<Assets<HtmlTemplate>>,
+    server: Res<AssetServer>,
 ) {
     let entity = trigger.entity;
-    let Ok((content_id, scope, mut text)) = nodes.get_mut(entity) else {
+    let Ok((content_id, scope, text, span)) = nodes.get_mut(entity) else {
         warn!("trying to compile content for {entity}, that does not have any");
         return;
     };
 
-    let Some((template, props)) = root
-        .get(**scope)
-        .ok()
-        .map(|(handle, props)| templates.get(&**handle).map(|d| (d, props)))
-        .flatten()
-    else {
+    let Some(template) = templates.get(content_id.template) else {
+        warn!("template of {entity} is not loaded");
+        return;
+    };
+
+    // a template root is its own scope
+    let scope = scope.map_or(entity, |scope| **scope);
+    let Ok(props) = contexts.get(scope) else {
         warn!("{entity} has no scope!");
         return;
     };
 
-    _ = template
+    let mut errors = vec![];
+    let Some(compiled) = template
         .content
-        .get(**content_id)
-        .map(|raw| compile_content(raw.trim(), &props))
-        .map(|compiled| **text = compiled);
+        .get(content_id.content)
+        .map(|raw| compile_content(raw, props, &mut errors))
+    else {
+        return;
+    };
+
+    if !errors.is_empty() {
+        let name = template_name(content_id.template, &templates, &server);
+        errors
+            .iter()
+            .for_each(|err| warn!("text of {entity} in `{name}` failed to compile, {err}"));
+    }
+
+    // unchanged text is not laid out again
+    match (text, span) {
+        (Some(mut text), _) => {
+            if **text != compiled {
+                **text = compiled;
+            }
+        }
+        (_, Some(mut span)) => {
+            if **span != compiled {
+                **span = compiled;
+            }
+        }
+        _ => warn!("{entity} is neither a text nor a span"),
+    }
 }
 
 #[derive(Event)]
@@ -65,67 +125,533 @@ pub struct CompileNodeEvent {
     pub entity: Entity,
 }
 
-fn compile_node(
-    trigger: On<CompileNodeEvent>,
+/// recompiles only the expressions of a node,
+/// that read one of the changed `keys`.
+#[derive(Event)]
+pub(crate) struct CompileKeysEvent {
+    pub entity: Entity,
+    pub keys: Vec<String>,
+}
+
+fn compile_node(trigger: On<CompileNodeEvent>, mut compiler: NodeCompiler) {
+    compiler.compile(trigger.entity, None);
+}
+
+fn compile_node_keys(trigger: On<CompileKeysEvent>, mut compiler: NodeCompiler) {
+    compiler.compile(trigger.entity, Some(&trigger.keys));
+}
+
+#[derive(SystemParam)]
+struct NodeCompiler<'w, 's> {
+    cmd: Commands<'w, 's>,
+    nodes: Query<'w, 's, (&'static mut HtmlStyle, &'static TemplateScope)>,
+    images: Query<'w, 's, &'static mut ImageNode>,
+    tags: Query<'w, 's, &'static mut Tags>,
+    transitions: Query<'w, 's, &'static mut StyleTransition>,
+    dynamic_styles: Query<'w, 's, &'static DynamicStyles>,
+    expressions: Query<'w, 's, &'static TemplateExpresions>,
+    contexts: Query<'w, 's, &'static TemplateProperties>,
+    media: Query<'w, 's, &'static UiMedia>,
+    html_nodes: Query<'w, 's, &'static HtmlNode>,
+    templates: Res<'w, Assets<HtmlTemplate>>,
+    server: Res<'w, AssetServer>,
+}
+
+impl NodeCompiler<'_, '_> {
+    /// compiles the expressions of a node, all of them
+    /// or only those reading one of the `keys`.
+    fn compile(&mut self, entity: Entity, keys: Option<&[String]>) {
+        let Ok((mut node_style, scope)) = self.nodes.get_mut(entity) else {
+            // unbuild nodes also complain
+            // warn!("Trying to compile a non ui node");
+            return;
+        };
+
+        // check owned properties aswell
+        let Some(context) = self
+            .contexts
+            .get(entity)
+            .ok()
+            .or(self.contexts.get(**scope).ok())
+        else {
+            warn!("Node has no context scope");
+            return;
+        };
+
+        let mut compiled_styles = vec![];
+        if let Ok(expressions) = self.expressions.get(entity) {
+            let mut adapter = AssetServerAdaptor {
+                server: &self.server,
+            };
+            expressions
+                .iter()
+                .enumerate()
+                .filter(|(_, expr)| {
+                    keys.is_none_or(|keys| expr.value.keys().iter().any(|key| keys.contains(key)))
+                })
+                .for_each(|(index, expr)| match expr.compile(context, &mut adapter) {
+                    Some(compiled) => {
+                        match compiled {
+                            crate::data::Attribute::Style(style_attr) => {
+                                let (rank, style_attr) = match self.media.get(entity) {
+                                    Ok(media) => {
+                                        (media.rank(&style_attr), media.resolve(style_attr))
+                                    }
+                                    Err(_) => (0, style_attr),
+                                };
+                                compiled_styles.push((rank, index, style_attr));
+                            }
+                            crate::data::Attribute::Action(action) => {
+                                action.self_insert(self.cmd.entity(entity))
+                            }
+                            crate::data::Attribute::Path(path) => {
+                                _ = self.images.get_mut(entity).map(|mut img| {
+                                    img.image = self.server.load(path);
+                                });
+                            }
+                            crate::data::Attribute::Tag(key, value) => {
+                                match self.tags.get_mut(entity) {
+                                    Ok(mut tags) => {
+                                        tags.insert(key, value);
+                                    }
+                                    Err(_) => {
+                                        warn!("node has to tags")
+                                    }
+                                }
+                            }
+                            rest => {
+                                warn!("attribute of this kind cannot be dynamic `{:?}`", rest);
+                            }
+                        };
+                    }
+                    None => {
+                        let name = self
+                            .html_nodes
+                            .get(**scope)
+                            .map(|node| template_name(&node.0, &self.templates, &self.server))
+                            .unwrap_or_default();
+                        match expr.value.eval(context) {
+                            Err(err) => warn!(
+                                "`{}` of {entity} in `{name}` failed to compile, {err}",
+                                expr.ident
+                            ),
+                            Ok(value) => warn!(
+                                "`{}` of {entity} in `{name}` failed to compile `{value}`",
+                                expr.ident
+                            ),
+                        }
+                    }
+                });
+        }
+
+        if compiled_styles.is_empty() {
+            return;
+        }
+
+        // larger breakpoints win
+        compiled_styles.sort_by_key(|(rank, ..)| *rank);
+        let compiled_styles = compiled_styles
+            .into_iter()
+            .map(|(_, index, style)| (index, style))
+            .collect::<Vec<_>>();
+
+        // --------------------
+        // transition from the previous values,
+        // the first compile is not animated
+        let previous = self.dynamic_styles.get(entity).ok();
+        let changed = previous.is_some_and(|previous| {
+            compiled_styles.iter().any(|(index, style)| {
+                !previous
+                    .iter()
+                    .find(|(i, _)| i == index)
+                    .is_some_and(|(_, prev)| prev.reflect_partial_eq(style).unwrap_or(false))
+            })
+        });
+
+        if node_style.computed.animate_changes && changed {
+            match self.transitions.get_mut(entity) {
+                // retarget a running transition, from where it is
+                Ok(mut transition) => {
+                    transition.from = transition.current(&node_style.timing(&[]));
+                    compiled_styles.iter().for_each(|(_, style)| {
+                        replace_style(&mut transition.styles, style.clone())
+                    });
+                    transition.timer = default();
+                }
+                Err(_) => {
+                    let mut from = node_style.clone();
+                    previous
+                        .iter()
+                        .flat_map(|previous| previous.iter())
+                        .for_each(|(_, style)| {
+                            from.add_style_attr(style.clone(), Some(&self.server))
+                        });
+
+                    self.cmd.entity(entity).insert(StyleTransition {
+                        from: from.computed,
+                        styles: compiled_styles.iter().map(|(_, style)| style.clone()).collect(),
+                        timer: default(),
+                    });
+                }
+            }
+        }
+
+        for (_, style) in compiled_styles.iter() {
+            node_style.add_style_attr(style.clone(), Some(&self.server));
+        }
+
+        // partial compiles keep the other styles
+        let mut dynamic = match keys {
+            Some(_) => previous.map(|previous| previous.0.clone()).unwrap_or_default(),
+            None => vec![],
+        };
+        for (index, style) in compiled_styles {
+            match dynamic.iter_mut().find(|(i, _)| *i == index) {
+                Some(entry) => entry.1 = style,
+                None => dynamic.push((index, style)),
+            }
+        }
+        self.cmd.entity(entity).insert(DynamicStyles(dynamic));
+    }
+}
+
+#[derive(Event)]
+pub struct CompileConditionEvent {
+    pub entity: Entity,
+}
+
+/// components kept on a conditional node,
+/// while its subtree is removed.
+#[derive(Bundle)]
+struct ConditionKeep {
+    pub parent: ChildOf,
+    pub scope: TemplateScope,
+    pub condition: UiCondition,
+    pub inside: InsideSlot,
+    pub id: UiId,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compile_condition(
+    trigger: On<CompileConditionEvent>,
     mut cmd: Commands,
-    mut nodes: Query<(&mut HtmlStyle, &TemplateScope)>,
-    mut images: Query<&mut ImageNode>,
-    mut tags: Query<&mut Tags>,
-    expressions: Query<&TemplateExpresions>,
-    contexts: Query<&TemplateProperties>,
+    mut conditions: Query<(&mut UiCondition, &TemplateScope)>,
+    mut subscriber: Query<&mut TemplatePropertySubscriber>,
+    properties: Query<&TemplateProperties>,
+    children: Query<&Children>,
+    sloted_nodes: Query<&InsideSlot>,
+    templates: Res<Assets<HtmlTemplate>>,
     server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    custom_comps: Res<ComponentBindings>,
 ) {
     let entity = trigger.entity;
-    let Ok((mut node_style, scope)) = nodes.get_mut(entity) else {
-        // unbuild nodes also complain
-        // warn!("Trying to compile a non ui node");
+    let Ok((mut condition, scope)) = conditions.get_mut(entity) else {
         return;
     };
 
-    // check owned properties aswell
-    let Some(context) = contexts.get(entity).ok().or(contexts.get(**scope).ok()) else {
-        warn!("Node has no context scope");
+    let Ok(props) = properties.get(**scope) else {
+        warn!("{entity} has no scope!");
         return;
     };
 
-    if let Ok(expressions) = expressions.get(entity) {
-        let mut adapter = AssetServerAdaptor { server: &server };
-        expressions
-            .iter()
-            .for_each(|expr| match expr.compile(context, &mut adapter) {
-                Some(compiled) => {
-                    match compiled {
-                        crate::data::Attribute::Style(style_attr) => {
-                            node_style.add_style_attr(style_attr, Some(&server))
-                        }
-                        crate::data::Attribute::Action(action) => {
-                            action.self_insert(cmd.entity(entity))
-                        }
-                        crate::data::Attribute::Path(path) => {
-                            _ = images.get_mut(entity).map(|mut img| {
-                                img.image = server.load(path);
-                            });
-                        }
-                        crate::data::Attribute::Tag(key, value) => match tags.get_mut(entity) {
-                            Ok(mut tags) => {
-                                tags.insert(key, value);
-                            }
-                            Err(_) => {
-                                warn!("node has to tags")
-                            }
-                        },
-                        rest => {
-                            warn!("attribute of this kind cannot be dynamic `{:?}`", rest);
-                        }
-                    };
-                }
-                None => {
-                    dbg!(context);
-                    warn!("expression failed to compile `{:?}`", expr);
-                    return;
+    let visible = match eval_condition(&condition.expression, props) {
+        Ok(visible) => visible,
+        Err(err) => {
+            let name = template_name(condition.template, &templates, &server);
+            warn!(
+                "`if=\"{{{}}}\"` of {entity} in `{name}` failed to compile, {err}",
+                condition.expression
+            );
+            false
+        }
+    };
+    if visible == condition.visible {
+        return;
+    }
+
+    condition.visible = visible;
+
+    // --------------------
+    // build the subtree
+    if visible {
+        let Some(node) = templates
+            .get(condition.template)
+            .map(|template| (template, template.find_node(condition.node)))
+        else {
+            warn!("template of conditional node {entity} is not loaded");
+            return;
+        };
+
+        let (template, Some(node)) = node else {
+            warn!("conditional node {entity} does not exist in its template");
+            return;
+        };
+
+        let mut builder = TemplateBuilder::new(
+            **scope,
+            condition.template,
+            cmd.reborrow(),
+            &server,
+            &mut texture_atlases,
+            &custom_comps,
+            template,
+            props,
+        );
+
+        builder.build_node(entity, node);
+        builder.finalize_relations();
+        cmd.trigger(CompileContextEvent { entity: **scope });
+        return;
+    }
+
+    // --------------------
+    // remove the subtree, but keep nodes
+    // that were moved into a slot by the caller
+    let descendants = children.iter_descendants(entity).collect::<Vec<_>>();
+    let sloted = descendants
+        .iter()
+        .filter(|desc| {
+            sloted_nodes
+                .get(**desc)
+                .is_ok_and(|slot| slot.owner == **scope)
+        })
+        .copied()
+        .collect::<Vec<_>>();
+
+    if !sloted.is_empty() {
+        let slot_holder = cmd
+            .spawn(Node {
+                display: Display::None,
+                ..default()
+            })
+            .add_children(&sloted)
+            .id();
+        cmd.entity(**scope).insert(UnslotedChildren(slot_holder));
+    }
+
+    if let Ok(mut subs) = subscriber.get_mut(**scope) {
+        subs.retain(|sub| !descendants.contains(sub));
+    }
+
+    cmd.entity(entity)
+        .despawn_related::<Children>()
+        .retain::<ConditionKeep>()
+        .insert(Node {
+            display: Display::None,
+            ..default()
+        });
+}
+
+#[derive(Event)]
+pub struct CompileLoopEvent {
+    pub entity: Entity,
+}
+
+/// diffs the list against the built elements. Elements of
+/// equal items are kept, moved ones are reordered. Every element
+/// owns a copy of the outer scope, which is compared and cloned
+/// per element on each compile, keep large lists flat.
+#[allow(clippy::too_many_arguments)]
+fn compile_loop(
+    trigger: On<CompileLoopEvent>,
+    mut cmd: Commands,
+    mut loops: Query<&mut UiLoop>,
+    mut properties: Query<&mut TemplateProperties>,
+    templates: Res<Assets<HtmlTemplate>>,
+    server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    custom_comps: Res<ComponentBindings>,
+) {
+    let entity = trigger.entity;
+    let Ok(mut ui_loop) = loops.get_mut(entity) else {
+        return;
+    };
+
+    let Some(outer) = properties.get(ui_loop.scope).ok().cloned() else {
+        warn!("{entity} has no scope!");
+        return;
+    };
+
+    let items = match outer.get(&ui_loop.repeat.key) {
+        Some(list) => list.items(),
+        None => {
+            let name = template_name(ui_loop.template, &templates, &server);
+            warn!(
+                "`for` of {entity} in `{name}` failed to compile, {}",
+                ExprError::UnknownProperty(ui_loop.repeat.key.clone())
+            );
+            vec![]
+        }
+    };
+
+    // --------------------
+    // match the items to the previous elements, equal
+    // items keep their nodes, wherever they moved to.
+    // Changed items reuse the remaining elements in order.
+    let before = ui_loop
+        .iterations
+        .iter()
+        .map(|iteration| iteration.scope)
+        .collect::<Vec<_>>();
+    let mut previous = std::mem::take(&mut ui_loop.iterations)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    let mut matched = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            previous
+                .get_mut(index)
+                .and_then(|iteration| iteration.take_if(|iteration| iteration.item == *item))
+        })
+        .collect::<Vec<_>>();
+    for (item, slot) in items.iter().zip(matched.iter_mut()) {
+        if slot.is_none() {
+            *slot = previous
+                .iter_mut()
+                .find_map(|iteration| iteration.take_if(|iteration| iteration.item == *item));
+        }
+    }
+    let mut remaining = previous.into_iter().flatten();
+    for slot in matched.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = remaining.next();
+    }
+
+    // --------------------
+    // remove elements
+    remaining.for_each(|iteration| {
+        iteration.nodes.iter().for_each(|node| {
+            cmd.entity(*node).despawn();
+        });
+        cmd.entity(iteration.scope).despawn();
+    });
+
+    let template = templates.get(ui_loop.template);
+    let node = template.and_then(|template| template.find_node(ui_loop.node));
+    if node.is_none() && matched.iter().any(Option::is_none) {
+        warn!("template of loop {entity} is not loaded");
+    }
+
+    for (index, (item, slot)) in items.iter().zip(matched).enumerate() {
+        let props = iteration_properties(&outer, &ui_loop.repeat, item, index);
+
+        // --------------------
+        // update existing elements, only
+        // recompile the ones that changed
+        if let Some(mut iteration) = slot {
+            if let Ok(mut current) = properties.get_mut(iteration.scope) {
+                if current.0 != props.0 {
+                    *current.bypass_change_detection() = props;
+                    cmd.trigger(CompileContextEvent {
+                        entity: iteration.scope,
+                    });
                 }
-            });
+            }
+            iteration.item = item.clone();
+            ui_loop.iterations.push(iteration);
+            continue;
+        }
+
+        // --------------------
+        // add elements
+        let (Some(template), Some(node)) = (template, node) else {
+            continue;
+        };
+
+        let scope = cmd.spawn(TemplateScope(ui_loop.scope)).id();
+        let mut builder = TemplateBuilder::new(
+            scope,
+            ui_loop.template,
+            cmd.reborrow(),
+            &server,
+            &mut texture_atlases,
+            &custom_comps,
+            template,
+            &props,
+        );
+
+        let nodes = builder.build_children(entity, &node.children);
+        builder.finalize_relations();
+
+        cmd.entity(scope).insert(props);
+        cmd.trigger(CompileContextEvent { entity: scope });
+        ui_loop.iterations.push(LoopIteration {
+            scope,
+            nodes,
+            item: item.clone(),
+        });
+    }
+
+    // --------------------
+    // restore the order of the items
+    let after = ui_loop.iterations.iter().map(|iteration| iteration.scope);
+    if after.ne(before) {
+        let order = ui_loop
+            .iterations
+            .iter()
+            .flat_map(|iteration| iteration.nodes.iter().copied())
+            .collect::<Vec<_>>();
+        cmd.queue(move |world: &mut World| {
+            if let Some(mut children) = world.get_mut::<Children>(entity) {
+                children.sort_by_key(|child| order.iter().position(|node| node == child));
+            }
+        });
+    }
+}
+
+/// the scope of a list element, a copy of the outer
+/// scope with `item` and `index`. Both shadow outer
+/// properties of the same name, like the index of an
+/// outer loop, name them `for="cell, col in {cells}"`.
+fn iteration_properties(
+    outer: &TemplateProperties,
+    repeat: &Repeat,
+    item: &PropertyValue,
+    index: usize,
+) -> TemplateProperties {
+    let mut props = outer.clone();
+    props
+        .set_typed(&repeat.item, item.clone())
+        .set(&repeat.index, &index.to_string());
+    props
+}
+
+/// list properties are comma separated, optionally
+/// wrapped in `[]`. Commas inside parentheses, brackets
+/// or quotes do not split, `[rgb(1,2,3), #fff]` has two items.
+pub(crate) fn split_list(input: &str) -> Vec<String> {
+    let input = input.trim();
+    let input = input
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+        .unwrap_or(input);
+
+    if input.trim().is_empty() {
+        return vec![];
     }
+
+    let mut items = vec![];
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+    for (pos, c) in input.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                items.push(input[start..pos].trim().to_string());
+                start = pos + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(input[start..].trim().to_string());
+    items
 }
 
 #[derive(Event)]
@@ -133,16 +659,40 @@ pub struct CompileContextEvent {
     pub entity: Entity,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn compile_context(
     trigger: On<CompileContextEvent>,
     expressions: Query<(&TemplateExpresions, Option<&TemplateScope>)>,
-    text_nodes: Query<(), With<ContentId>>,
+    kinds: Query<(Has<ContentId>, Has<UiCondition>, Has<UiLoop>)>,
     subscriber: Query<&TemplatePropertySubscriber>,
+    mut indices: Query<&mut PropertyIndex>,
+    subscriber_keys: SubscriberKeys,
     mut properties: Query<&mut TemplateProperties>,
+    slot_scopes: Query<&SlotScope>,
+    scopes: Query<&TemplateScope>,
+    html_nodes: Query<&HtmlNode, With<FullyBuild>>,
+    templates: Res<Assets<HtmlTemplate>>,
     mut cmd: Commands,
     server: Res<AssetServer>,
 ) {
     let entity = trigger.entity;
+
+    // ----------
+    // slot scopes start with the callers properties,
+    // slot properties are compiled on top
+    if let Ok(slot_scope) = slot_scopes.get(entity) {
+        let mut caller = scopes
+            .get(slot_scope.owner)
+            .ok()
+            .and_then(|caller| properties.get(**caller).ok())
+            .cloned()
+            .unwrap_or_default();
+        caller.extend_str(&slot_scope.defs);
+        _ = properties.get_mut(entity).map(|mut context| {
+            *context.bypass_change_detection() = caller;
+        });
+    }
+
     if let Ok((expressions, scope)) = expressions.get(entity) {
         // ----------
         // problem: compiling props on template root nodes
@@ -152,14 +702,21 @@ fn compile_context(
         // compile
         if let Some(parent_context) = scope.map(|s| properties.get(**s).ok()).flatten() {
             let mut adapter = AssetServerAdaptor { server: &server };
-            let mut compiled_defintions = vec![];
+            let mut compiled_definitions = vec![];
             for expr in expressions.iter() {
                 // --------------------
                 //compile from parent
                 match expr.compile(parent_context, &mut adapter) {
                     Some(compiled) => match compiled {
                         crate::data::Attribute::PropertyDefinition(key, value) => {
-                            compiled_defintions.push((key, value));
+                            // a single property is passed on typed
+                            let value = expr
+                                .value
+                                .as_property()
+                                .and_then(|key| parent_context.get(key))
+                                .cloned()
+                                .unwrap_or(value.into());
+                            compiled_definitions.push((key, value));
                         }
                         _ => {
                             // error!("cannot compile to unimplementd attribute `{:?}`", compiled);
@@ -167,17 +724,33 @@ fn compile_context(
                     },
                     None => {
                         // check owned props
-                        if let Ok(owned_ctx) = properties.get(entity) {
-                            match expr.compile(owned_ctx, &mut adapter) {
-                                Some(crate::data::Attribute::PropertyDefinition(key, value)) => {
-                                    compiled_defintions.push((key, value));
-                                }
-                                _ => {
-                                    // error!("cannot compile to unimplementd attribute `{:?}`", expr);
-                                }
+                        match properties
+                            .get(entity)
+                            .ok()
+                            .and_then(|owned_ctx| expr.compile(owned_ctx, &mut adapter))
+                        {
+                            Some(crate::data::Attribute::PropertyDefinition(key, value)) => {
+                                compiled_definitions.push((key, value.into()));
+                            }
+                            Some(_) => {
+                                // error!("cannot compile to unimplementd attribute `{:?}`", expr);
+                            }
+                            None => {
+                                let name = scope
+                                    .and_then(|scope| html_nodes.get(**scope).ok())
+                                    .map(|node| template_name(&node.0, &templates, &server))
+                                    .unwrap_or_default();
+                                let err = expr
+                                    .value
+                                    .eval(parent_context)
+                                    .err()
+                                    .map(|err| err.to_string())
+                                    .unwrap_or_default();
+                                warn!(
+                                    "`{}` of {entity} in `{name}` failed to compile, {err}",
+                                    expr.ident
+                                );
                             }
-                        } else {
-                            error!("cannot compile: {:#?}", expr);
                         }
                     }
                 }
@@ -185,48 +758,553 @@ fn compile_context(
                 // compile from self
             }
             _ = properties.get_mut(entity).map(|mut context| {
-                context.extend(compiled_defintions.into_iter());
+                context
+                    .bypass_change_detection()
+                    .extend(compiled_definitions);
             });
         };
     };
 
-    if let Ok(subs) = subscriber.get(entity) {
-        for sub in subs.iter() {
-            if *sub != entity && properties.get(*sub).is_ok() {
-                cmd.trigger(CompileContextEvent { entity: *sub });
-            } else {
-                cmd.trigger(CompileNodeEvent { entity: *sub });
+    let subs = subscriber
+        .get(entity)
+        .map(|subs| subs.0.as_slice())
+        .unwrap_or_default();
+    let node = html_nodes.get(entity).ok();
+    if subs.is_empty() && node.is_none() {
+        return;
+    }
+
+    // ----------
+    // only the subscribers reading a changed property
+    let outdated = match properties.get(entity) {
+        Ok(props) => {
+            let mut created = None;
+            let index = match indices.get_mut(entity) {
+                Ok(index) => index.into_inner(),
+                Err(_) => created.insert(PropertyIndex::default()),
+            };
+            let changed = index.changed_keys(props);
+
+            // properties of build templates and
+            // components against their declaration
+            if let Some(node) = node {
+                let errors = index.property_errors(changed.as_deref(), || {
+                    templates
+                        .get(&node.0)
+                        .map(|template| template.validate_properties(props))
+                        .unwrap_or_default()
+                });
+                if !errors.is_empty() {
+                    let name = template_name(&node.0, &templates, &server);
+                    errors
+                        .iter()
+                        .for_each(|err| error!("`{name}` {entity}: {err}"));
+                }
             }
-            if text_nodes.get(*sub).is_ok() {
-                cmd.trigger(CompileContentEvent { entity: *sub });
+
+            let outdated = index.outdated(subs, changed, &subscriber_keys);
+            if let Some(index) = created {
+                cmd.entity(entity).insert(index);
             }
+            outdated
+        }
+        Err(_) => subs.iter().map(|sub| (*sub, None)).collect(),
+    };
+
+    for (sub, keys) in outdated {
+        let (is_text, is_conditional, is_loop) = kinds.get(sub).unwrap_or_default();
+        if is_conditional {
+            cmd.trigger(CompileConditionEvent { entity: sub });
+        }
+        if is_loop {
+            cmd.trigger(CompileLoopEvent { entity: sub });
+        }
+        if sub != entity && properties.get(sub).is_ok() {
+            cmd.trigger(CompileContextEvent { entity: sub });
+        } else {
+            match keys {
+                Some(keys) => cmd.trigger(CompileKeysEvent { entity: sub, keys }),
+                None => cmd.trigger(CompileNodeEvent { entity: sub }),
+            }
+        }
+        if is_text {
+            cmd.trigger(CompileContentEvent { entity: sub });
         }
     }
 }
 
-// this is bad, only 1 var allowed
-pub(crate) fn compile_content(input: &str, defs: &TemplateProperties) -> String {
+/// the `<name>` of a template or its path
+pub(crate) fn template_name(
+    id: impl Into<AssetId<HtmlTemplate>>,
+    templates: &Assets<HtmlTemplate>,
+    server: &AssetServer,
+) -> String {
+    let id = id.into();
+    templates
+        .get(id)
+        .and_then(|template| template.name.clone())
+        .or_else(|| server.get_path(id).map(|path| path.to_string()))
+        .unwrap_or_else(|| "template".to_string())
+}
+
+/// subscribers with the changed keys to recompile, `None` for all
+type Outdated = Vec<(Entity, Option<Vec<String>>)>;
+
+/// the subscribers of a scope by the property keys they
+/// read, with the properties of the last compile.
+#[derive(Component, Default)]
+struct PropertyIndex {
+    subscribers: Vec<Entity>,
+    by_key: HashMap<String, Vec<Entity>>,
+    /// subscribers reading every property
+    any_key: Vec<Entity>,
+    compiled: Option<HashMap<String, PropertyValue>>,
+    /// the properties were checked against their declaration
+    validated: bool,
+}
+
+impl PropertyIndex {
+    /// the subscribers to recompile with the changed keys they
+    /// read. New subscribers and the first compile recompile all.
+    fn outdated(
+        &mut self,
+        subs: &[Entity],
+        changed: Option<Vec<String>>,
+        subscriber_keys: &SubscriberKeys,
+    ) -> Outdated {
+        let added = match self.subscribers.as_slice() == subs {
+            true => vec![],
+            false => {
+                let added = subs
+                    .iter()
+                    .filter(|sub| !self.subscribers.contains(sub))
+                    .copied()
+                    .collect::<Vec<_>>();
+                self.rebuild(subs, subscriber_keys);
+                added
+            }
+        };
+
+        let Some(changed) = changed else {
+            return subs.iter().map(|sub| (*sub, None)).collect();
+        };
+
+        subs.iter()
+            .filter_map(|sub| {
+                if added.contains(sub) || self.any_key.contains(sub) {
+                    return Some((*sub, None));
+                }
+
+                let keys = changed
+                    .iter()
+                    .filter(|key| self.by_key.get(*key).is_some_and(|subs| subs.contains(sub)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!keys.is_empty()).then_some((*sub, Some(keys)))
+            })
+            .collect()
+    }
+
+    fn rebuild(&mut self, subs: &[Entity], subscriber_keys: &SubscriberKeys) {
+        self.subscribers = subs.to_vec();
+        self.by_key.clear();
+        self.any_key.clear();
+
+        for sub in subs.iter() {
+            match subscriber_keys.keys(*sub) {
+                Some(keys) => keys.into_iter().for_each(|key| {
+                    self.by_key.entry(key).or_default().push(*sub);
+                }),
+                None => self.any_key.push(*sub),
+            }
+        }
+    }
+
+    /// the property errors to report, all of them the first time,
+    /// then only those of changed keys. Skips the validation
+    /// if no key changed.
+    fn property_errors(
+        &mut self,
+        changed: Option<&[String]>,
+        validate: impl FnOnce() -> Vec<PropertyError>,
+    ) -> Vec<PropertyError> {
+        let changed = changed.filter(|_| std::mem::replace(&mut self.validated, true));
+        if changed.is_some_and(|keys| keys.is_empty()) {
+            return vec![];
+        }
+
+        validate()
+            .into_iter()
+            .filter(|err| changed.is_none_or(|keys| keys.iter().any(|key| key == err.key())))
+            .collect()
+    }
+
+    /// the keys changed since the last compile,
+    /// `None` if there was none.
+    fn changed_keys(&mut self, props: &TemplateProperties) -> Option<Vec<String>> {
+        let previous = self.compiled.replace(props.0.clone())?;
+        let mut changed = props
+            .iter()
+            .filter(|(key, value)| previous.get(*key) != Some(*value))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        changed.extend(
+            previous
+                .keys()
+                .filter(|key| !props.contains_key(*key))
+                .cloned(),
+        );
+        Some(changed)
+    }
+}
+
+/// the property keys a subscriber reads
+#[derive(SystemParam)]
+struct SubscriberKeys<'w, 's> {
+    expressions: Query<'w, 's, &'static TemplateExpresions>,
+    contents: Query<'w, 's, &'static ContentId>,
+    conditions: Query<'w, 's, &'static UiCondition>,
+    loops: Query<'w, 's, (), With<UiLoop>>,
+    slot_scopes: Query<'w, 's, (), With<SlotScope>>,
+    templates: Res<'w, Assets<HtmlTemplate>>,
+}
+
+impl SubscriberKeys<'_, '_> {
+    /// `None` if it reads every property, list
+    /// elements and slots copy the whole scope.
+    fn keys(&self, entity: Entity) -> Option<Vec<String>> {
+        if self.loops.contains(entity) || self.slot_scopes.contains(entity) {
+            return None;
+        }
+
+        let mut keys = vec![];
+        if let Ok(expressions) = self.expressions.get(entity) {
+            expressions
+                .iter()
+                .for_each(|expr| keys.extend(expr.value.keys()));
+        }
+
+        if let Ok(content) = self.contents.get(entity) {
+            let raw = self
+                .templates
+                .get(content.template)?
+                .content
+                .get(content.content)?;
+            keys.extend(content_keys(raw)?);
+        }
+
+        if let Ok(condition) = self.conditions.get(entity) {
+            let (_, expr) = parse_expression::<nom::error::Error<&[u8]>>(
+                condition.expression.as_bytes(),
+            )
+            .ok()?;
+            expr.keys(&mut keys);
+        }
+
+        Some(keys)
+    }
+}
+
+/// the property keys of every `{...}` expression in
+/// the text, `None` if any expression is invalid.
+fn content_keys(input: &str) -> Option<Vec<String>> {
+    let (_, segments) = parse_segments::<nom::error::Error<&[u8]>>(input.as_bytes()).ok()?;
+    let mut keys = vec![];
+    for segment in segments {
+        if let Segment::Expression(source) = segment {
+            let (_, expr) = parse_expression::<nom::error::Error<&[u8]>>(source).ok()?;
+            expr.keys(&mut keys);
+        }
+    }
+    Some(keys)
+}
+
+/// evaluates an `if` expression against the template
+/// properties. A missing property on its own is falsy.
+pub(crate) fn eval_condition(
+    expression: &str,
+    defs: &TemplateProperties,
+) -> Result<bool, ExprError> {
+    match eval_source(expression.as_bytes(), defs) {
+        Err(ExprError::UnknownProperty(key)) if expression.trim() == key => Ok(false),
+        result => result.map(|value| value.is_truthy()),
+    }
+}
+
+/// evaluates every `{...}` expression in the text, failing
+/// expressions are left empty and their errors collected.
+pub(crate) fn compile_content(
+    input: &str,
+    defs: &TemplateProperties,
+    errors: &mut Vec<ExprError>,
+) -> String {
+    let Ok((rest, segments)) = parse_segments::<nom::error::Error<&[u8]>>(input.as_bytes()) else {
+        return input.to_string();
+    };
+
     let mut compiled = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Literal(literal) => compiled.push_str(&String::from_utf8_lossy(literal)),
+            Segment::Expression(source) => match eval_source(source, defs) {
+                Ok(value) => compiled.push_str(&value.to_string()),
+                Err(err) => errors.push(err),
+            },
+        }
+    }
+
+    compiled.push_str(&String::from_utf8_lossy(rest));
+    compiled
+}
 
-    let parts: Result<(&str, (&str, &str)), nom::Err<nom::error::Error<&str>>> = tuple((
-        take_until("{"),
-        delimited(tag("{"), preceded(multispace0, is_not("}")), tag("}")),
-    ))(input);
+fn eval_source(source: &[u8], defs: &TemplateProperties) -> Result<Value, ExprError> {
+    let (_, expr) = parse_expression::<nom::error::Error<&[u8]>>(source)
+        .map_err(|_| ExprError::Invalid(String::from_utf8_lossy(source).to_string()))?;
+    expr.eval(defs)
+}
 
-    let Ok((input, (literal, key))) = parts else {
-        compiled.push_str(input);
-        return compiled;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::{AttrTokens, StyleAttr},
+        expr::{Expr, Interpolation, InterpolationPart},
     };
+    use std::time::Duration;
+    use test_case::test_case;
+
+    #[test_case("a, b, c" => vec!["a", "b", "c"]; "plain")]
+    #[test_case("[a, b]" => vec!["a", "b"]; "brackets")]
+    #[test_case("[]" => Vec::<String>::new(); "empty")]
+    #[test_case("[rgb(1,2,3), #fff]" => vec!["rgb(1,2,3)", "#fff"]; "parentheses")]
+    #[test_case("'a, b', \"c, d\", e" => vec!["'a, b'", "\"c, d\"", "e"]; "quotes")]
+    #[test_case("[[1, 2], [3]]" => vec!["[1, 2]", "[3]"]; "nested lists")]
+    fn test_split_list(input: &str) -> Vec<String> {
+        split_list(input)
+    }
 
-    compiled.push_str(literal);
+    #[test]
+    fn test_animate_changes() {
+        let mut app = App::new();
+        app.add_plugins((
+            bevy::app::TaskPoolPlugin::default(),
+            bevy::asset::AssetPlugin::default(),
+        ));
+        app.init_asset::<HtmlTemplate>();
+        app.add_observer(compile_node);
 
-    if let Some(value) = defs.get(key.trim_end()) {
-        compiled.push_str(value);
+        let scope = app
+            .world_mut()
+            .spawn(TemplateProperties::default().with("w", "10px"))
+            .id();
+        let mut style = HtmlStyle::default();
+        style.computed.animate_changes = true;
+        style.computed.delay = 1.;
+        let entity = app
+            .world_mut()
+            .spawn((
+                style,
+                TemplateScope(scope),
+                TemplateExpresions(vec![AttrTokens {
+                    prefix: None,
+                    ident: "width".into(),
+                    key: "{w}".into(),
+                    value: Interpolation(vec![InterpolationPart::Expr(Expr::Var("w".into()))]),
+                }]),
+            ))
+            .id();
+
+        let compile = |app: &mut App, width: &str| {
+            app.world_mut()
+                .get_mut::<TemplateProperties>(scope)
+                .unwrap()
+                .insert("w".into(), width.into());
+            app.world_mut().trigger(CompileNodeEvent { entity });
+            app.world_mut().flush();
+        };
+
+        // the first compile is not animated
+        compile(&mut app, "10px");
+        assert!(app.world().get::<StyleTransition>(entity).is_none());
+
+        compile(&mut app, "20px");
+        let transition = app.world().get::<StyleTransition>(entity).unwrap();
+        assert_eq!(transition.from.node.width, Val::Px(10.));
+        assert!(matches!(
+            transition.styles[..],
+            [StyleAttr::Width(Val::Px(20.))]
+        ));
+
+        // halfway, the new target starts where the node is
+        let style = app.world().get::<HtmlStyle>(entity).unwrap().clone();
+        let timing = style.timing(&[]);
+        app.world_mut()
+            .get_mut::<StyleTransition>(entity)
+            .unwrap()
+            .timer
+            .tick(true, Duration::from_millis(500), &timing);
+
+        compile(&mut app, "40px");
+        let transition = app.world().get::<StyleTransition>(entity).unwrap();
+        assert_eq!(transition.from.node.width, Val::Px(15.));
+        assert!(matches!(
+            transition.styles[..],
+            [StyleAttr::Width(Val::Px(40.))]
+        ));
+        assert_eq!(transition.timer.fraction(), 0.);
     }
 
-    if input.len() > 0 {
-        compiled.push_str(&compile_content(input, defs));
+    #[test]
+    fn test_property_errors() {
+        let errors = || {
+            vec![
+                PropertyError::Missing("hp".into()),
+                PropertyError::Max("mana".into(), 120., 100.),
+            ]
+        };
+        let changed = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+
+        // all errors once, even if nothing changed
+        let mut index = PropertyIndex::default();
+        assert_eq!(index.property_errors(Some(&[]), errors).len(), 2);
+
+        // then only changed keys
+        assert!(index.property_errors(Some(&[]), errors).is_empty());
+        assert!(index
+            .property_errors(Some(&changed(&["title"])), errors)
+            .is_empty());
+        assert_eq!(
+            index.property_errors(Some(&changed(&["title", "mana"])), errors),
+            vec![PropertyError::Max("mana".into(), 120., 100.)]
+        );
+
+        // without previous properties, all are reported
+        assert_eq!(index.property_errors(None, errors).len(), 2);
     }
 
-    compiled
+    #[test]
+    fn test_recompile_changed_properties() {
+        use crate::testing::{add_template, test_app, text};
+
+        #[derive(Resource, Default)]
+        struct Compiled(usize);
+
+        let mut app = test_app();
+        app.init_resource::<Compiled>();
+        let handle = add_template(&mut app, r#"<template><text>{title}</text></template>"#);
+        let root = app
+            .world_mut()
+            .spawn((
+                HtmlNode(handle),
+                TemplateProperties::default().with("title", "first"),
+            ))
+            .id();
+        app.add_observer(
+            move |trigger: On<CompileContextEvent>, mut compiled: ResMut<Compiled>| {
+                compiled.0 += (trigger.entity == root) as usize;
+            },
+        );
+        let compiled =
+            |app: &mut App| std::mem::take(&mut app.world_mut().resource_mut::<Compiled>().0);
+
+        // only the build compiles the new node
+        app.update();
+        assert_eq!(compiled(&mut app), 1);
+        assert_eq!(text(&app, root), "first");
+
+        app.update();
+        assert_eq!(compiled(&mut app), 0);
+
+        app.world_mut()
+            .get_mut::<TemplateProperties>(root)
+            .unwrap()
+            .set("title", "second");
+        app.update();
+        assert_eq!(compiled(&mut app), 1);
+        assert_eq!(text(&app, root), "second");
+    }
+
+    #[test]
+    fn test_loop_keeps_equal_items() {
+        use crate::testing::{spawn_template, test_app, text};
+
+        let mut app = test_app();
+        let root = spawn_template(
+            &mut app,
+            r#"<template><node for="item in {items}"><text>{item}</text></node></template>"#,
+            TemplateProperties::default().with("items", "a, b, c"),
+        );
+
+        let nodes = |app: &mut App| {
+            let ui_loop = app
+                .world_mut()
+                .query::<&UiLoop>()
+                .single(app.world())
+                .unwrap();
+            ui_loop
+                .iterations
+                .iter()
+                .flat_map(|iteration| iteration.nodes.clone())
+                .collect::<Vec<_>>()
+        };
+        let set_items = |app: &mut App, items: &str| {
+            app.world_mut()
+                .get_mut::<TemplateProperties>(root)
+                .unwrap()
+                .set("items", items);
+            app.update();
+            app.update();
+        };
+
+        let [a, b, c] = nodes(&mut app)[..] else {
+            panic!("three elements");
+        };
+        assert_eq!(text(&app, b), "b");
+
+        // removing the first item keeps the others
+        set_items(&mut app, "b, c");
+        assert_eq!(nodes(&mut app), vec![b, c]);
+        assert!(app.world().get_entity(a).is_err());
+
+        // appending builds only the new item
+        set_items(&mut app, "b, c, d");
+        let [b2, c2, d] = nodes(&mut app)[..] else {
+            panic!("three elements");
+        };
+        assert_eq!((b2, c2), (b, c));
+        assert_eq!(text(&app, d), "d");
+
+        // moved items are reordered
+        set_items(&mut app, "d, b, c");
+        assert_eq!(nodes(&mut app), vec![d, b, c]);
+        let ui_loop = app
+            .world_mut()
+            .query_filtered::<Entity, With<UiLoop>>()
+            .single(app.world())
+            .unwrap();
+        let children = app.world().get::<Children>(ui_loop).unwrap();
+        assert_eq!(children.to_vec(), vec![d, b, c]);
+    }
+
+    #[test_case("show" => Ok(false); "missing property")]
+    #[test_case("count > 1" => Ok(true); "comparison")]
+    #[test_case("count > maxx" => Err(ExprError::UnknownProperty("maxx".into())); "typo")]
+    #[test_case("count / 0" => Err(ExprError::DivisionByZero); "division by zero")]
+    fn test_eval_condition(expression: &str) -> Result<bool, ExprError> {
+        eval_condition(
+            expression,
+            &TemplateProperties::default().with("count", "2"),
+        )
+    }
+
+    #[test]
+    fn test_compile_content_errors() {
+        let props = TemplateProperties::default()
+            .with("hp", "5")
+            .with("max_hp", "10");
+        let mut errors = vec![];
+        let text = compile_content("{hp} / {max_hp}, {hp / max_hpp}%", &props, &mut errors);
+        assert_eq!(text, "5 / 10, %");
+        assert_eq!(errors, vec![ExprError::UnknownProperty("max_hpp".into())]);
+    }
 }
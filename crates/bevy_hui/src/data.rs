@@ -1,7 +1,10 @@
 use crate::adaptor::AssetLoadAdaptor;
 use crate::animation::{AnimationDirection, Atlas};
+use crate::error::PropertyError;
+use crate::expr::Interpolation;
 use crate::prelude::*;
 use crate::util::{SlotId, SlotMap};
+use crate::value::PropertyValue;
 use bevy::ecs::system::EntityCommands;
 use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
@@ -14,13 +17,35 @@ pub enum NodeType {
     Node,
     Image,
     Text,
+    Span,
     Button,
     Slot,
+    Fragment,
+    Block,
     Template,
     Property,
     Custom(String),
 }
 
+impl NodeType {
+    /// the tag name, stylesheet selectors match against it.
+    pub fn name(&self) -> &str {
+        match self {
+            NodeType::Node => "node",
+            NodeType::Image => "image",
+            NodeType::Text => "text",
+            NodeType::Span => "span",
+            NodeType::Button => "button",
+            NodeType::Slot => "slot",
+            NodeType::Fragment => "fragment",
+            NodeType::Block => "block",
+            NodeType::Template => "template",
+            NodeType::Property => "property",
+            NodeType::Custom(name) => name,
+        }
+    }
+}
+
 /// a single nodes data
 #[derive(Debug, Default, Reflect)]
 #[reflect]
@@ -36,6 +61,13 @@ pub struct XNode {
     pub tags: HashMap<String, String>,
     pub defs: HashMap<String, String>,
     pub event_listener: Vec<Action>,
+    pub condition: Option<String>,
+    pub repeat: Option<Repeat>,
+    /// names of the classes in `class="primary large"`
+    pub classes: Vec<String>,
+    /// the named slot of the parent component,
+    /// this node is placed into.
+    pub slot: Option<String>,
     pub content_id: SlotId,
     pub node_type: NodeType,
     #[reflect(ignore)]
@@ -48,11 +80,230 @@ pub struct XNode {
 #[reflect]
 pub struct HtmlTemplate {
     pub name: Option<String>,
-    pub properties: HashMap<String, String>,
+    /// path of the base template, resolved by the loader.
+    pub extends: Option<String>,
+    /// paths of shared class files, `<style src=".."/>`
+    pub style_imports: Vec<String>,
+    /// class styles from `<style>` blocks
+    pub classes: HashMap<String, Vec<StyleAttr>>,
+    /// the declared `<property>` tags
+    pub properties: HashMap<String, PropertyDef>,
     pub root: Vec<XNode>,
     pub content: SlotMap<String>,
 }
 
+impl HtmlTemplate {
+    /// find a node anywhere in the template tree
+    /// by its uuid.
+    pub fn find_node(&self, uuid: u64) -> Option<&XNode> {
+        self.root.iter().find_map(|node| node.find(uuid))
+    }
+
+    /// the styles of a node, class styles in the order
+    /// of its `class` attribute followed by inline styles.
+    pub fn node_styles(&self, node: &XNode) -> Vec<StyleAttr> {
+        let mut styles = self.class_styles(&node.classes);
+        styles.extend(node.styles.iter().cloned());
+        styles
+    }
+
+    /// the styles of the given classes, in order. Classes
+    /// without template styles may be used by stylesheets.
+    pub fn class_styles(&self, classes: &[String]) -> Vec<StyleAttr> {
+        classes
+            .iter()
+            .filter_map(|class| self.classes.get(class))
+            .flat_map(|class_styles| class_styles.iter().cloned())
+            .collect()
+    }
+
+    /// checks the properties against their declarations,
+    /// required properties, types and number ranges.
+    pub fn validate_properties(&self, props: &TemplateProperties) -> Vec<PropertyError> {
+        self.properties
+            .iter()
+            .filter_map(|(key, def)| match props.get(key) {
+                Some(value) => def.validate(key, value).err(),
+                None => def
+                    .required
+                    .then(|| PropertyError::Missing(key.clone())),
+            })
+            .collect()
+    }
+
+    /// adds class styles, styles of an already
+    /// existing class are appended.
+    pub fn add_classes(&mut self, classes: impl IntoIterator<Item = (String, Vec<StyleAttr>)>) {
+        classes.into_iter().for_each(|(class, styles)| {
+            self.classes.entry(class).or_default().extend(styles);
+        });
+    }
+
+    /// merges this template into the template it `extends`.
+    /// Each `<block name="..">` of the base is replaced by
+    /// the block of the same name, others keep their default content.
+    pub fn merge_into(self, mut base: HtmlTemplate) -> HtmlTemplate {
+        let HtmlTemplate {
+            name,
+            classes,
+            properties,
+            root,
+            content,
+            ..
+        } = self;
+
+        let mut blocks = HashMap::default();
+        root.into_iter()
+            .for_each(|node| match (&node.node_type, node.name.clone()) {
+                (NodeType::Block, Some(block)) => {
+                    blocks.insert(block, node);
+                }
+                _ => warn!("extending templates only contain named `<block>` nodes, ignoring the rest"),
+            });
+
+        base.root
+            .iter_mut()
+            .for_each(|node| node.override_blocks(&mut blocks, &content, &mut base.content));
+
+        blocks
+            .keys()
+            .for_each(|block| warn!("block `{block}` does not exist in the base template"));
+
+        base.name = name.or(base.name);
+        base.properties.extend(properties);
+        base.add_classes(classes);
+
+        let mut uuid = 0;
+        base.root
+            .iter_mut()
+            .for_each(|node| crate::parse::assign_uuids(node, &mut uuid));
+        base
+    }
+}
+
+impl XNode {
+    fn find(&self, uuid: u64) -> Option<&XNode> {
+        if self.uuid == uuid {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(uuid))
+    }
+
+    fn override_blocks(
+        &mut self,
+        blocks: &mut HashMap<String, XNode>,
+        from: &SlotMap<String>,
+        into: &mut SlotMap<String>,
+    ) {
+        if let (NodeType::Block, Some(name)) = (&self.node_type, &self.name) {
+            if let Some(mut block) = blocks.remove(name) {
+                block.move_content(from, into);
+                self.children = block.children;
+                return;
+            }
+        }
+
+        self.children
+            .iter_mut()
+            .for_each(|child| child.override_blocks(blocks, from, into));
+    }
+
+    /// moves the text content of this
+    /// subtree into another templates content.
+    fn move_content(&mut self, from: &SlotMap<String>, into: &mut SlotMap<String>) {
+        if let Some(text) = from.get(self.content_id).cloned() {
+            self.content_id = into.insert(text);
+        }
+
+        self.children
+            .iter_mut()
+            .for_each(|child| child.move_content(from, into));
+    }
+}
+
+/// a declared template property,
+/// `<property name="hp" type="float" required="true" min="0"/>`
+#[derive(Debug, Clone, Default, PartialEq, Reflect)]
+#[reflect]
+pub struct PropertyDef {
+    /// the text content of the tag
+    pub default: Option<String>,
+    pub kind: PropertyKind,
+    pub required: bool,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl PropertyDef {
+    pub fn validate(&self, key: &str, value: &PropertyValue) -> Result<(), PropertyError> {
+        if !value.is_kind(self.kind) {
+            return Err(PropertyError::Type(
+                key.to_string(),
+                self.kind,
+                value.to_string(),
+            ));
+        }
+
+        let Some(number) = value.as_number() else {
+            return Ok(());
+        };
+
+        if let Some(min) = self.min.filter(|min| number < *min) {
+            return Err(PropertyError::Min(key.to_string(), number, min));
+        }
+
+        if let Some(max) = self.max.filter(|max| number > *max) {
+            return Err(PropertyError::Max(key.to_string(), number, max));
+        }
+
+        Ok(())
+    }
+}
+
+/// the `type` of a declared property
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+#[reflect]
+pub enum PropertyKind {
+    #[default]
+    Any,
+    String,
+    Float,
+    Int,
+    Bool,
+    Color,
+    Val,
+    List,
+    Entity,
+}
+
+impl std::fmt::Display for PropertyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            PropertyKind::Any => "any value",
+            PropertyKind::String => "a string",
+            PropertyKind::Float => "a float",
+            PropertyKind::Int => "an int",
+            PropertyKind::Bool => "a bool",
+            PropertyKind::Color => "a color",
+            PropertyKind::Val => "a `Val`",
+            PropertyKind::List => "a list",
+            PropertyKind::Entity => "an entity",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// a `for="item in {items}"` loop, the children
+/// of the node are build once per list element.
+/// `for="item, i in {items}"` names the index `i`.
+#[derive(Debug, Clone, Default, Reflect, PartialEq)]
+#[reflect]
+pub struct Repeat {
+    pub item: String,
+    pub index: String,
+    pub key: String,
+}
+
 /// any valid attribute that can be found
 /// on nodes.
 #[derive(Debug, Clone, Reflect)]
@@ -68,22 +319,37 @@ pub enum Attribute {
     Id(String),
     Watch(String),
     Tag(String, String),
+    Condition(String),
+    Repeat(Repeat),
+    Slot(String),
+    Class(Vec<String>),
 }
 
-/// raw attribute
+/// raw attribute, `key` holds the source
+/// of the parsed value.
 #[derive(Debug, Reflect, PartialEq, Clone)]
 #[reflect]
 pub struct AttrTokens {
     pub prefix: Option<String>,
     pub ident: String,
     pub key: String,
+    #[reflect(ignore)]
+    pub value: Interpolation,
 }
 
 impl AttrTokens {
     pub fn compile(&self, props: &TemplateProperties, loader: &mut impl AssetLoadAdaptor) -> Option<Attribute> {
-        let Some(prop_val) = props.get(&self.key) else {
-            return None;
-        };
+        // typed properties skip the parser
+        if let Some(attr) = self
+            .value
+            .as_property()
+            .and_then(|key| props.get(key))
+            .and_then(|value| value.to_attribute(self.prefix.as_deref(), &self.ident))
+        {
+            return Some(attr);
+        }
+
+        let prop_val = self.value.eval(props).ok()?;
 
         let (_, attr) = match crate::parse::attribute_from_parts::<nom::error::VerboseError<&[u8]>>(
             self.prefix.as_ref().map(|s| s.as_bytes()),
@@ -223,11 +489,17 @@ pub enum StyleAttr {
     Hover(#[reflect(ignore)] Box<StyleAttr>),
     Pressed(#[reflect(ignore)] Box<StyleAttr>),
     Active(#[reflect(ignore)] Box<StyleAttr>),
+    /// any other prefix, like the breakpoint
+    /// in `md:width="50%"`
+    Prefixed(String, #[reflect(ignore)] Box<StyleAttr>),
 
     // -----
     // animations
     Delay(f32),
+    LeaveDelay(f32),
     Easing(EaseFunction),
+    Transition(Vec<PropertyTransition>),
+    AnimateChanges(bool),
     Atlas(Option<Atlas>),
     Duration(f32),
     Iterations(i64),
@@ -247,6 +519,60 @@ pub enum StyleAttr {
     Pickable((bool, bool)),
 }
 
+impl StyleAttr {
+    /// the name of an interpolated style,
+    /// as used by `transition`
+    pub fn property_name(&self) -> Option<&'static str> {
+        let name = match self {
+            StyleAttr::Left(_) => "left",
+            StyleAttr::Right(_) => "right",
+            StyleAttr::Top(_) => "top",
+            StyleAttr::Bottom(_) => "bottom",
+            StyleAttr::Width(_) => "width",
+            StyleAttr::Height(_) => "height",
+            StyleAttr::MinWidth(_) => "min_width",
+            StyleAttr::MinHeight(_) => "min_height",
+            StyleAttr::MaxWidth(_) => "max_width",
+            StyleAttr::MaxHeight(_) => "max_height",
+            StyleAttr::AspectRatio(_) => "aspect_ratio",
+            StyleAttr::Margin(_) => "margin",
+            StyleAttr::Padding(_) => "padding",
+            StyleAttr::Border(_) => "border",
+            StyleAttr::BorderColor(_) => "border_color",
+            StyleAttr::BorderRadius(_) => "border_radius",
+            StyleAttr::Outline(_) => "outline",
+            StyleAttr::FlexGrow(_) => "flex_grow",
+            StyleAttr::FlexShrink(_) => "flex_shrink",
+            StyleAttr::FlexBasis(_) => "flex_basis",
+            StyleAttr::RowGap(_) => "row_gap",
+            StyleAttr::ColumnGap(_) => "column_gap",
+            StyleAttr::FontSize(_) => "font_size",
+            StyleAttr::FontColor(_) => "font_color",
+            StyleAttr::Background(_) => "background",
+            StyleAttr::ShadowColor(_) => "shadow_color",
+            StyleAttr::ShadowOffset(..) => "shadow_offset",
+            StyleAttr::ShadowSpread(_) => "shadow_spread",
+            StyleAttr::ShadowBlur(_) => "shadow_blur",
+            StyleAttr::TextShadow(_) => "text_shadow",
+            StyleAttr::ImageColor(_) => "image_color",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// the own timing of a property,
+/// `transition="background 200ms cubic_out"`
+#[derive(Debug, Clone, PartialEq, Reflect)]
+#[reflect]
+pub struct PropertyTransition {
+    pub property: String,
+    /// in seconds
+    pub duration: f32,
+    /// falls back to the `ease` of the node
+    pub easing: Option<EaseFunction>,
+}
+
 impl Default for StyleAttr {
     fn default() -> Self {
         StyleAttr::Display(Display::None)
@@ -1,3 +1,4 @@
+use crate::data::PropertyKind;
 use owo_colors::OwoColorize;
 use std::fmt::Write;
 use thiserror::Error;
@@ -15,6 +16,63 @@ pub enum ParseError {
 
     #[error("Failed with incomplete data")]
     Incomplete,
+
+    #[error("failed to load base template `{0}`: {1}")]
+    Extends(String, String),
+
+    #[error("templates extend each other in a cycle `{0}`")]
+    ExtendsCycle(String),
+}
+
+/// runtime errors of `{...}` expressions
+#[derive(Error, Debug, PartialEq)]
+pub enum ExprError {
+    #[error("unknown property `{0}`")]
+    UnknownProperty(String),
+
+    #[error("`{0}` is not a number")]
+    NotANumber(String),
+
+    #[error("unknown function `{0}`")]
+    UnknownFunction(String),
+
+    #[error("function `{0}` expects {1} arguments")]
+    Arity(String, &'static str),
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("invalid expression `{0}`")]
+    Invalid(String),
+}
+
+/// a property value, that does not
+/// match its `<property>` declaration
+#[derive(Error, Debug, PartialEq)]
+pub enum PropertyError {
+    #[error("required property `{0}` is missing")]
+    Missing(String),
+
+    #[error("property `{0}` expects {1}, got `{2}`")]
+    Type(String, PropertyKind, String),
+
+    #[error("property `{0}` is {1}, below the minimum of {2}")]
+    Min(String, f32, f32),
+
+    #[error("property `{0}` is {1}, above the maximum of {2}")]
+    Max(String, f32, f32),
+}
+
+impl PropertyError {
+    /// the key of the property
+    pub fn key(&self) -> &str {
+        match self {
+            PropertyError::Missing(key)
+            | PropertyError::Type(key, ..)
+            | PropertyError::Min(key, ..)
+            | PropertyError::Max(key, ..) => key,
+        }
+    }
 }
 
 pub enum HtmlError<'a> {
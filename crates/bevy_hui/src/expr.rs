@@ -0,0 +1,890 @@
+use crate::build::TemplateProperties;
+use crate::error::ExprError;
+use crate::value::PropertyValue;
+use std::cell::Cell;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{char, multispace0},
+    combinator::{cut, map, opt, recognize},
+    error::{context, ContextError, ErrorKind, ParseError},
+    multi::{many0, separated_list0},
+    number::complete::float,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+
+/// a parsed `{...}` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Var(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+/// the result of an expression. String properties
+/// are coerced to numbers where needed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f32),
+    Str(String),
+    Bool(bool),
+}
+
+impl Value {
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Str(s) => s.trim().parse::<f32>().ok(),
+            Value::Bool(_) => None,
+        }
+    }
+
+    /// `""`, `false`, `0` and `none` are falsy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.,
+            Value::Bool(b) => *b,
+            Value::Str(s) => !matches!(s.trim(), "" | "false" | "0" | "none"),
+        }
+    }
+
+    fn try_number(&self) -> Result<f32, ExprError> {
+        self.as_number()
+            .ok_or_else(|| ExprError::NotANumber(self.to_string()))
+    }
+}
+
+/// other typed properties are used as their string
+impl From<&PropertyValue> for Value {
+    fn from(value: &PropertyValue) -> Self {
+        match value {
+            PropertyValue::Number(n) => Value::Number(*n),
+            PropertyValue::Bool(b) => Value::Bool(*b),
+            PropertyValue::Str(s) => Value::Str(s.clone()),
+            other => Value::Str(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // 4 decimals hide float artifacts like `30.000002`
+            Value::Number(n) => {
+                let rounded = format!("{n:.4}");
+                let trimmed = match rounded.contains('.') {
+                    true => rounded.trim_end_matches('0').trim_end_matches('.'),
+                    false => &rounded,
+                };
+                match trimmed {
+                    "-0" => write!(f, "0"),
+                    n => write!(f, "{n}"),
+                }
+            }
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl Expr {
+    /// evaluates the expression against the template properties.
+    pub fn eval(&self, props: &TemplateProperties) -> Result<Value, ExprError> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Var(key) => props
+                .get(key)
+                .map(Value::from)
+                .ok_or_else(|| ExprError::UnknownProperty(key.clone())),
+            Expr::Not(expr) => Ok(Value::Bool(!expr.eval(props)?.is_truthy())),
+            Expr::Neg(expr) => Ok(Value::Number(-expr.eval(props)?.try_number()?)),
+            Expr::Ternary(cond, then, otherwise) => match cond.eval(props)?.is_truthy() {
+                true => then.eval(props),
+                false => otherwise.eval(props),
+            },
+            Expr::Binary(BinaryOp::And, lhs, rhs) => Ok(Value::Bool(
+                lhs.eval(props)?.is_truthy() && rhs.eval(props)?.is_truthy(),
+            )),
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => Ok(Value::Bool(
+                lhs.eval(props)?.is_truthy() || rhs.eval(props)?.is_truthy(),
+            )),
+            Expr::Binary(op, lhs, rhs) => eval_binary(*op, lhs.eval(props)?, rhs.eval(props)?),
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(props))
+                    .collect::<Result<Vec<_>, _>>()?;
+                eval_call(name, args)
+            }
+        }
+    }
+
+    /// collects the property keys the expression reads.
+    pub fn keys(&self, keys: &mut Vec<String>) {
+        match self {
+            Expr::Literal(_) => (),
+            Expr::Var(key) => {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+            Expr::Not(expr) | Expr::Neg(expr) => expr.keys(keys),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.keys(keys);
+                rhs.keys(keys);
+            }
+            Expr::Ternary(cond, then, otherwise) => {
+                cond.keys(keys);
+                then.keys(keys);
+                otherwise.keys(keys);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.keys(keys)),
+        }
+    }
+}
+
+fn eval_binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, ExprError> {
+    let numbers = lhs.as_number().zip(rhs.as_number());
+    let value = match op {
+        // concat, unless both sides are numbers
+        BinaryOp::Add => match numbers {
+            Some((a, b)) => Value::Number(a + b),
+            None => Value::Str(format!("{lhs}{rhs}")),
+        },
+        BinaryOp::Sub => Value::Number(lhs.try_number()? - rhs.try_number()?),
+        BinaryOp::Mul => Value::Number(lhs.try_number()? * rhs.try_number()?),
+        BinaryOp::Div | BinaryOp::Rem => {
+            let (a, b) = (lhs.try_number()?, rhs.try_number()?);
+            if b == 0. {
+                return Err(ExprError::DivisionByZero);
+            }
+            match op {
+                BinaryOp::Div => Value::Number(a / b),
+                _ => Value::Number(a % b),
+            }
+        }
+        BinaryOp::Eq | BinaryOp::Ne => {
+            let equal = match numbers {
+                Some((a, b)) => a == b,
+                None => lhs.to_string() == rhs.to_string(),
+            };
+            Value::Bool(equal == matches!(op, BinaryOp::Eq))
+        }
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match numbers {
+                Some((a, b)) => a.partial_cmp(&b),
+                None => Some(lhs.to_string().cmp(&rhs.to_string())),
+            };
+            let Some(ordering) = ordering else {
+                return Ok(Value::Bool(false));
+            };
+            Value::Bool(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        BinaryOp::And | BinaryOp::Or => unreachable!("short circuit"),
+    };
+    Ok(value)
+}
+
+/// checks the name and the argument count of a
+/// function call, the error is a parser context.
+fn check_call(name: &str, args: usize) -> Result<(), &'static str> {
+    match (name, args) {
+        ("min" | "max" | "format", 1..) | ("clamp", 3) => Ok(()),
+        ("abs" | "round" | "floor" | "ceil", 1) => Ok(()),
+        ("min" | "max" | "format", _) => Err("function expects at least 1 argument"),
+        ("clamp", _) => Err("function `clamp` expects 3 arguments"),
+        ("abs" | "round" | "floor" | "ceil", _) => Err("function expects 1 argument"),
+        _ => Err("unknown function, expected min, max, clamp, abs, round, floor, ceil or format"),
+    }
+}
+
+fn eval_call(name: &str, args: Vec<Value>) -> Result<Value, ExprError> {
+    let numbers = || {
+        args.iter()
+            .map(|arg| arg.try_number())
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    match (name, args.len()) {
+        ("min", 1..) => Ok(Value::Number(
+            numbers()?.into_iter().fold(f32::INFINITY, f32::min),
+        )),
+        ("max", 1..) => Ok(Value::Number(
+            numbers()?.into_iter().fold(f32::NEG_INFINITY, f32::max),
+        )),
+        ("clamp", 3) => {
+            let n = numbers()?;
+            Ok(Value::Number(n[0].max(n[1]).min(n[2])))
+        }
+        ("abs", 1) => Ok(Value::Number(args[0].try_number()?.abs())),
+        ("round", 1) => Ok(Value::Number(args[0].try_number()?.round())),
+        ("floor", 1) => Ok(Value::Number(args[0].try_number()?.floor())),
+        ("ceil", 1) => Ok(Value::Number(args[0].try_number()?.ceil())),
+        // `format('{} of {}', a, b)`
+        ("format", 1..) => {
+            let mut args = args.into_iter();
+            let fmt = args.next().map(|f| f.to_string()).unwrap_or_default();
+            let mut out = String::new();
+            let mut parts = fmt.split("{}");
+            if let Some(first) = parts.next() {
+                out.push_str(first);
+            }
+            for part in parts {
+                if let Some(arg) = args.next() {
+                    out.push_str(&arg.to_string());
+                }
+                out.push_str(part);
+            }
+            Ok(Value::Str(out))
+        }
+        ("min" | "max" | "format", _) => Err(ExprError::Arity(name.to_string(), "at least 1")),
+        ("clamp", _) => Err(ExprError::Arity(name.to_string(), "3")),
+        ("abs" | "round" | "floor" | "ceil", _) => Err(ExprError::Arity(name.to_string(), "1")),
+        _ => Err(ExprError::UnknownFunction(name.to_string())),
+    }
+}
+
+/// a value with any number of `{...}` expressions
+/// mixed with literal text, like `{pad}px 10px`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Interpolation(pub Vec<InterpolationPart>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    Literal(String),
+    Expr(Expr),
+}
+
+impl Interpolation {
+    /// evaluates all expressions and joins them with the literals.
+    pub fn eval(&self, props: &TemplateProperties) -> Result<String, ExprError> {
+        let mut out = String::new();
+        for part in self.0.iter() {
+            match part {
+                InterpolationPart::Literal(literal) => out.push_str(literal),
+                InterpolationPart::Expr(expr) => out.push_str(&expr.eval(props)?.to_string()),
+            }
+        }
+        Ok(out)
+    }
+
+    /// the key of a value that is a single
+    /// property, like `{bar_color}`.
+    pub fn as_property(&self) -> Option<&str> {
+        match self.0.as_slice() {
+            [InterpolationPart::Expr(Expr::Var(key))] => Some(key),
+            _ => None,
+        }
+    }
+
+    /// the property keys of all expressions
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = vec![];
+        for part in self.0.iter() {
+            if let InterpolationPart::Expr(expr) = part {
+                expr.keys(&mut keys);
+            }
+        }
+        keys
+    }
+}
+
+/// parses a value into an `Interpolation`,
+/// `None` if the value has no expressions.
+pub(crate) fn parse_interpolation<'a, E>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], Option<Interpolation>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (rest, segments) = parse_segments(input)?;
+    if !segments
+        .iter()
+        .any(|segment| matches!(segment, Segment::Expression(_)))
+    {
+        return Ok((rest, None));
+    }
+
+    let mut parts = vec![];
+    for segment in segments {
+        match segment {
+            Segment::Literal(literal) => parts.push(InterpolationPart::Literal(
+                crate::parse::decode_entities(literal, false),
+            )),
+            Segment::Expression(source) => {
+                parts.push(InterpolationPart::Expr(parse_encoded_expression(source)?));
+            }
+        }
+    }
+
+    Ok((rest, Some(Interpolation(parts))))
+}
+
+/// parses the source of an attribute expression, entities like
+/// `&lt;` are decoded first. Errors point at the raw source.
+pub(crate) fn parse_encoded_expression<'a, E>(source: &'a [u8]) -> Result<Expr, nom::Err<E>>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let decoded = crate::parse::decode_entities(source, false);
+    if source == decoded.as_bytes() {
+        let (_, expr) = context("invalid expression", parse_expression)(source)?;
+        return Ok(expr);
+    }
+
+    // decoded source is owned, point at the raw value instead
+    parse_expression::<nom::error::Error<&[u8]>>(decoded.as_bytes())
+        .map(|(_, expr)| expr)
+        .map_err(|_| {
+            let err = E::from_error_kind(source, ErrorKind::Verify);
+            nom::Err::Failure(E::add_context(source, "invalid expression", err))
+        })
+}
+
+/// text, split into literal parts and `{...}` expressions
+#[derive(Debug, PartialEq)]
+pub(crate) enum Segment<'a> {
+    Literal(&'a [u8]),
+    Expression(&'a [u8]),
+}
+
+/// splits text into literals and expressions.
+/// `{{` and `}}` are escaped braces.
+pub(crate) fn parse_segments<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Vec<Segment<'a>>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    many0(alt((
+        map(take_while1(|b: u8| b != b'{' && b != b'}'), Segment::Literal),
+        map(alt((tag("{{"), tag("}}"))), |brace: &[u8]| {
+            Segment::Literal(&brace[..1])
+        }),
+        map(tag("}"), Segment::Literal),
+        map(
+            preceded(
+                tag("{"),
+                cut(context(
+                    "unclosed `{` in expression",
+                    terminated(take_expression, tag("}")),
+                )),
+            ),
+            Segment::Expression,
+        ),
+    )))(input)
+}
+
+/// takes everything up to the closing `}`,
+/// ignoring braces inside of quoted strings.
+fn take_expression<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let mut quote = None;
+    for (i, b) in input.iter().enumerate() {
+        match (quote, *b) {
+            (Some(q), b) if b == q => quote = None,
+            (Some(_), _) => (),
+            (None, b'\'' | b'"') => quote = Some(*b),
+            (None, b'}') => return Ok((&input[i..], &input[..i])),
+            _ => (),
+        }
+    }
+    Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::TakeUntil)))
+}
+
+type Parser<'a, O, E> = fn(&'a [u8]) -> IResult<&'a [u8], O, E>;
+
+/// nesting levels of parentheses, ternaries and
+/// unary operators an expression may have.
+const MAX_DEPTH: usize = 64;
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// runs a parser one nesting level deeper, fails
+/// past `MAX_DEPTH` instead of overflowing the stack.
+fn nested<'a, O, E>(input: &'a [u8], parser: Parser<'a, O, E>) -> IResult<&'a [u8], O, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+    let result = match depth < MAX_DEPTH {
+        true => parser(input),
+        false => Err(nom::Err::Failure(E::add_context(
+            input,
+            "expression is nested too deep",
+            E::from_error_kind(input, ErrorKind::TooLarge),
+        ))),
+    };
+    DEPTH.with(|current| current.set(depth));
+    result
+}
+
+/// parses a complete expression, the content of `{...}`.
+pub fn parse_expression<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (rest, expr) = delimited(multispace0, parse_ternary, multispace0)(input)?;
+    if !rest.is_empty() {
+        let err = E::from_error_kind(rest, ErrorKind::Eof);
+        return Err(nom::Err::Failure(E::add_context(
+            rest,
+            "unexpected token in expression",
+            err,
+        )));
+    }
+    Ok((rest, expr))
+}
+
+fn ws<'a, O, E, F>(inner: F) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], O, E>
+where
+    E: ParseError<&'a [u8]>,
+    F: FnMut(&'a [u8]) -> IResult<&'a [u8], O, E>,
+{
+    delimited(multispace0, inner, multispace0)
+}
+
+fn parse_ternary<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    nested(input, parse_ternary_inner)
+}
+
+fn parse_ternary_inner<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (input, cond) = parse_or(input)?;
+    let (input, branches) = opt(preceded(
+        ws(tag("?")),
+        cut(context(
+            "ternary expects `cond ? a : b`",
+            tuple((parse_ternary, ws(tag(":")), parse_ternary)),
+        )),
+    ))(input)?;
+
+    match branches {
+        Some((then, _, otherwise)) => Ok((
+            input,
+            Expr::Ternary(Box::new(cond), Box::new(then), Box::new(otherwise)),
+        )),
+        None => Ok((input, cond)),
+    }
+}
+
+/// left associative chain of binary operators
+fn fold_binary<'a, E>(
+    input: &'a [u8],
+    operand: Parser<'a, Expr, E>,
+    operator: Parser<'a, BinaryOp, E>,
+) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (mut input, mut lhs) = operand(input)?;
+    loop {
+        let (rest, _) = multispace0(input)?;
+        let (rest, op) = match operator(rest) {
+            Ok(ok) => ok,
+            Err(nom::Err::Error(_)) => return Ok((input, lhs)),
+            Err(err) => return Err(err),
+        };
+        let (rest, _) = multispace0(rest)?;
+        let (rest, rhs) = cut(context("missing right hand side of operator", operand))(rest)?;
+        lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        input = rest;
+    }
+}
+
+fn parse_or<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    fold_binary(input, parse_and, |i| map(tag("||"), |_| BinaryOp::Or)(i))
+}
+
+fn parse_and<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    fold_binary(input, parse_equality, |i| map(tag("&&"), |_| BinaryOp::And)(i))
+}
+
+fn parse_equality<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    fold_binary(input, parse_comparison, |i| {
+        alt((
+            map(tag("=="), |_| BinaryOp::Eq),
+            map(tag("!="), |_| BinaryOp::Ne),
+        ))(i)
+    })
+}
+
+fn parse_comparison<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    fold_binary(input, parse_additive, |i| {
+        alt((
+            map(tag("<="), |_| BinaryOp::Le),
+            map(tag(">="), |_| BinaryOp::Ge),
+            map(tag("<"), |_| BinaryOp::Lt),
+            map(tag(">"), |_| BinaryOp::Gt),
+        ))(i)
+    })
+}
+
+fn parse_additive<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    fold_binary(input, parse_multiplicative, |i| {
+        alt((
+            map(tag("+"), |_| BinaryOp::Add),
+            map(tag("-"), |_| BinaryOp::Sub),
+        ))(i)
+    })
+}
+
+fn parse_multiplicative<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    fold_binary(input, parse_unary, |i| {
+        alt((
+            map(tag("*"), |_| BinaryOp::Mul),
+            map(tag("/"), |_| BinaryOp::Div),
+            map(tag("%"), |_| BinaryOp::Rem),
+        ))(i)
+    })
+}
+
+fn parse_unary<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    nested(input, parse_unary_inner)
+}
+
+fn parse_unary_inner<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    alt((
+        map(preceded(ws(char('!')), parse_unary), |e| Expr::Not(Box::new(e))),
+        map(preceded(ws(char('-')), parse_unary), |e| Expr::Neg(Box::new(e))),
+        parse_primary,
+    ))(input)
+}
+
+fn parse_primary<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    alt((
+        delimited(
+            char('('),
+            ws(parse_ternary),
+            context("missing closing `)`", cut(char(')'))),
+        ),
+        map(parse_string_literal, |s| Expr::Literal(Value::Str(s))),
+        parse_identifier_expr,
+        map(float, |n| Expr::Literal(Value::Number(n))),
+    ))(input)
+}
+
+fn parse_string_literal<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], String, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    map(
+        alt((
+            delimited(
+                char('\''),
+                take_while(|b: u8| b != b'\''),
+                context("unclosed string", cut(char('\''))),
+            ),
+            delimited(
+                char('"'),
+                take_while(|b: u8| b != b'"'),
+                context("unclosed string", cut(char('"'))),
+            ),
+        )),
+        |s: &[u8]| String::from_utf8_lossy(s).to_string(),
+    )(input)
+}
+
+/// property keys, may contain dots and hyphens like
+/// `my-key`. A hyphen followed by a letter is part of
+/// the key, subtract properties with spaces `a - b`.
+fn parse_identifier<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    recognize(pair(
+        take_while1(|b: u8| b.is_ascii_alphabetic() || b == b'_'),
+        many0(alt((
+            take_while1(|b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'.'),
+            recognize(pair(
+                tag("-"),
+                take_while1(|b: u8| b.is_ascii_alphabetic() || b == b'_'),
+            )),
+        ))),
+    ))(input)
+}
+
+/// bools, function calls and properties
+fn parse_identifier_expr<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Expr, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let start = input;
+    let (input, ident) = parse_identifier(input)?;
+    let ident = String::from_utf8_lossy(ident).to_string();
+
+    match ident.as_str() {
+        "true" => return Ok((input, Expr::Literal(Value::Bool(true)))),
+        "false" => return Ok((input, Expr::Literal(Value::Bool(false)))),
+        _ => (),
+    };
+
+    let (input, args) = opt(preceded(
+        preceded(multispace0, char('(')),
+        cut(terminated(
+            separated_list0(ws(char(',')), ws(parse_ternary)),
+            context("missing closing `)` of function call", char(')')),
+        )),
+    ))(input)?;
+
+    match args {
+        Some(args) => match check_call(&ident, args.len()) {
+            Ok(()) => Ok((input, Expr::Call(ident, args))),
+            Err(ctx) => Err(nom::Err::Failure(E::add_context(
+                start,
+                ctx,
+                E::from_error_kind(start, ErrorKind::Verify),
+            ))),
+        },
+        None => Ok((input, Expr::Var(ident))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::VerboseError;
+    use test_case::test_case;
+
+    fn props() -> TemplateProperties {
+        TemplateProperties::default()
+            .with("count", "3")
+            .with("progress", "0.5")
+            .with("title", "Hello")
+            .with("is_open", "true")
+            .with("empty", "")
+            .with("player.name", "Ferris")
+            .with("my-key", "hyphen")
+    }
+
+    #[test_case("count" => "3"; "property")]
+    #[test_case("count + 1" => "4"; "add")]
+    #[test_case("progress * 100" => "50"; "multiply")]
+    #[test_case("1 + 2 * 3" => "7"; "precedence")]
+    #[test_case("(1 + 2) * 3" => "9"; "parens")]
+    #[test_case("-count + 10 % 4" => "-1"; "unary and rem")]
+    #[test_case("title + ' World'" => "Hello World"; "concat")]
+    #[test_case("'#' + count" => "#3"; "concat number")]
+    #[test_case("count > 2 && !empty" => "true"; "logic")]
+    #[test_case("count == 3 || false" => "true"; "equality")]
+    #[test_case("title != 'Hello'" => "false"; "string equality")]
+    #[test_case("is_open ? '#FFF' : '#000'" => "#FFF"; "ternary")]
+    #[test_case("empty ? 1 : count < 3 ? 2 : 3" => "3"; "nested ternary")]
+    #[test_case("min(count, 2, 5)" => "2"; "min")]
+    #[test_case("max(count, 2)" => "3"; "max")]
+    #[test_case("clamp(count * 10, 0, 20)" => "20"; "clamp")]
+    #[test_case("format('{} of {}', count, 10)" => "3 of 10"; "format")]
+    #[test_case("player.name" => "Ferris"; "dotted key")]
+    #[test_case("my-key" => "hyphen"; "hyphenated key")]
+    #[test_case("count-1" => "2"; "subtract number")]
+    #[test_case("count - progress" => "2.5"; "subtract property")]
+    #[test_case("0.1 + 0.2" => "0.3"; "float artifacts")]
+    #[test_case("progress * 0.3 * 100" => "15"; "rounded")]
+    #[test_case("1 / 3" => "0.3333"; "precision")]
+    #[test_case("-0 * 1" => "0"; "negative zero")]
+    fn test_eval(input: &str) -> String {
+        let (_, expr) = parse_expression::<VerboseError<_>>(input.as_bytes()).unwrap();
+        expr.eval(&props()).unwrap().to_string()
+    }
+
+    #[test_case("count +"; "missing operand")]
+    #[test_case("(count"; "unclosed paren")]
+    #[test_case("'abc"; "unclosed string")]
+    #[test_case("count count"; "trailing token")]
+    fn test_parse_expression_error(input: &str) {
+        assert!(parse_expression::<VerboseError<_>>(input.as_bytes()).is_err());
+    }
+
+    #[test_case("maxx(1, 2)" => "unknown function, expected min, max, clamp, abs, round, floor, ceil or format"; "unknown function")]
+    #[test_case("count + clamp(1, 2)" => "function `clamp` expects 3 arguments"; "clamp arity")]
+    #[test_case("abs()" => "function expects 1 argument"; "abs arity")]
+    #[test_case("min()" => "function expects at least 1 argument"; "min arity")]
+    fn test_parse_call_error(input: &str) -> &'static str {
+        let Err(nom::Err::Failure(err)) = parse_expression::<VerboseError<_>>(input.as_bytes())
+        else {
+            panic!("`{input}` should fail");
+        };
+        err.errors
+            .iter()
+            .find_map(|(_, kind)| match kind {
+                nom::error::VerboseErrorKind::Context(ctx) => Some(*ctx),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_eval_unknown_function() {
+        let expr = Expr::Call("nope".into(), vec![]);
+        assert_eq!(
+            expr.eval(&props()),
+            Err(ExprError::UnknownFunction("nope".into()))
+        );
+    }
+
+    #[test_case(10 => true; "shallow")]
+    #[test_case(1000 => false; "too deep")]
+    fn test_nesting_depth(depth: usize) -> bool {
+        let parens = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        let unary = format!("{}1", "!".repeat(depth * 2));
+        let parsed = parse_expression::<VerboseError<_>>(parens.as_bytes()).is_ok();
+        assert_eq!(parse_expression::<VerboseError<_>>(unary.as_bytes()).is_ok(), parsed);
+        parsed
+    }
+
+    #[test_case("missing" => ExprError::UnknownProperty("missing".into()); "unknown property")]
+    #[test_case("title * 2" => ExprError::NotANumber("Hello".into()); "not a number")]
+    #[test_case("count / 0" => ExprError::DivisionByZero; "division by zero")]
+    fn test_eval_error(input: &str) -> ExprError {
+        let (_, expr) = parse_expression::<VerboseError<_>>(input.as_bytes()).unwrap();
+        expr.eval(&props()).unwrap_err()
+    }
+
+    #[test_case("count" => vec!["count"]; "property")]
+    #[test_case("'text' + 1" => Vec::<&str>::new(); "literals")]
+    #[test_case("is_open ? max(count, progress) : -count" => vec!["is_open", "count", "progress"]; "nested")]
+    #[test_case("player.name + title" => vec!["player.name", "title"]; "dotted key")]
+    fn test_keys(input: &str) -> Vec<String> {
+        let (_, expr) = parse_expression::<VerboseError<_>>(input.as_bytes()).unwrap();
+        let mut keys = vec![];
+        expr.keys(&mut keys);
+        keys
+    }
+
+    #[test_case("{pad}px 10px" => "3px 10px"; "literal suffix")]
+    #[test_case("icons/{title}.png" => "icons/Hello.png"; "path")]
+    #[test_case("{count}px {count * 2}px" => "3px 6px"; "multiple")]
+    fn test_interpolation(input: &str) -> String {
+        let props = props().with("pad", "3");
+        let (_, interpolation) = parse_interpolation::<VerboseError<_>>(input.as_bytes()).unwrap();
+        interpolation.unwrap().eval(&props).unwrap()
+    }
+
+    fn typed_props() -> TemplateProperties {
+        TemplateProperties::default()
+            .with_typed("hp", 0.75)
+            .with_typed("alive", true)
+            .with_typed("width", bevy::ui::Val::Percent(50.))
+            .with_typed("color", bevy::color::Color::srgb(1., 0., 0.))
+            .with_typed("items", vec!["a", "b"])
+    }
+
+    #[test_case("hp * 100" => "75"; "number")]
+    #[test_case("alive ? 'yes' : 'no'" => "yes"; "bool")]
+    #[test_case("'w: ' + width" => "w: 50%"; "val")]
+    #[test_case("color" => "#FF0000"; "color")]
+    #[test_case("items" => "a, b"; "list")]
+    fn test_eval_typed(input: &str) -> String {
+        let (_, expr) = parse_expression::<VerboseError<_>>(input.as_bytes()).unwrap();
+        expr.eval(&typed_props()).unwrap().to_string()
+    }
+
+    #[test_case("{width} 10px" => "50% 10px"; "val")]
+    #[test_case("{hp * 10}px" => "7.5px"; "number")]
+    fn test_interpolation_typed(input: &str) -> String {
+        let (_, interpolation) = parse_interpolation::<VerboseError<_>>(input.as_bytes()).unwrap();
+        interpolation.unwrap().eval(&typed_props()).unwrap()
+    }
+
+    #[test_case("{width}" => Some("width".to_string()); "single property")]
+    #[test_case("{width}px" => None; "mixed")]
+    #[test_case("{width * 2}" => None; "expression")]
+    fn test_as_property(input: &str) -> Option<String> {
+        let (_, interpolation) = parse_interpolation::<VerboseError<_>>(input.as_bytes()).unwrap();
+        interpolation.unwrap().as_property().map(|key| key.to_string())
+    }
+
+    #[test]
+    fn test_segments() {
+        let (rest, segments) =
+            parse_segments::<VerboseError<_>>(b"{count} of {format('{}', 1)}!").unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Expression(b"count"),
+                Segment::Literal(b" of "),
+                Segment::Expression(b"format('{}', 1)"),
+                Segment::Literal(b"!"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces() {
+        let (_, segments) = parse_segments::<VerboseError<_>>(b"{{count}} {count}}").unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Literal(b"{"),
+                Segment::Literal(b"count"),
+                Segment::Literal(b"}"),
+                Segment::Literal(b" "),
+                Segment::Expression(b"count"),
+                Segment::Literal(b"}"),
+            ]
+        );
+    }
+}
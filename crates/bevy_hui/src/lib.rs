@@ -2,6 +2,9 @@
 #![allow(rustdoc::redundant_explicit_links)]
 #![doc = include_str!("../../../README.md")]
 
+// the derive macro refers to `::bevy_hui`
+extern crate self as bevy_hui;
+
 use bevy::app::{App, Plugin, Update};
 use animation::run_animations;
 
@@ -12,10 +15,16 @@ mod build;
 mod compile;
 mod data;
 mod error;
+mod expr;
 mod load;
+mod media;
 mod parse;
+mod props;
 mod styles;
+mod stylesheet;
+mod theme;
 mod util;
+mod value;
 mod adaptor;
 
 pub mod prelude {
@@ -25,18 +34,31 @@ pub mod prelude {
     };
     pub use crate::build::{
         HtmlNode, OnUiChange, OnUiEnter, OnUiExit, OnUiPress, OnUiSpawn, Tags, TemplateProperties,
-        TemplateScope, UiId, UiTarget, UiWatch,
+        TemplateScope, UiCondition, UiId, UiTarget, UiWatch,
     };
     pub use crate::compile::{CompileContextEvent, CompileNodeEvent};
-    pub use crate::data::{Action, Attribute, HtmlTemplate, NodeType, StyleAttr};
-    pub use crate::error::ParseError;
+    pub use crate::data::{
+        Action, Attribute, HtmlTemplate, NodeType, PropertyDef, PropertyKind, StyleAttr,
+    };
+    pub use crate::error::{ExprError, ParseError, PropertyError};
     pub use crate::error::VerboseHtmlError;
     pub use crate::parse::parse_template;
-    pub use crate::styles::{HoverTimer, HtmlStyle, InteractionTimer, PressedTimer, UiActive};
+    pub use crate::props::{HuiProps, HuiPropsAppExt, UiSync};
+    pub use bevy_hui_derive::HuiProps;
+    pub use crate::styles::{
+        ActiveTimer, DeclaredText, HoverTimer, HtmlStyle, HuiStateAppExt, InteractionTimer,
+        NodeStyles, PressedTimer, StateTimers, StyleTransition, TransitionTiming, UiActive,
+        UiClassList, UiStates,
+    };
+    pub use crate::stylesheet::{HuiStylesheet, HuiStylesheetPlugin, HuiStylesheets, UiSelector};
+    pub use crate::media::{HuiBreakpoints, UiMedia};
+    pub use crate::theme::HuiTheme;
+    pub use crate::value::PropertyValue;
     pub use crate::HuiPlugin;
     pub use crate::adaptor::AssetServerAdaptor;
 }
 pub struct HuiPlugin;
+
 impl Plugin for HuiPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
@@ -45,6 +67,88 @@ impl Plugin for HuiPlugin {
             bindings::BindingPlugin,
             styles::TransitionPlugin,
             compile::CompilePlugin,
+            stylesheet::StylesheetPlugin,
+            theme::ThemePlugin,
+            media::MediaPlugin,
         )).add_systems(Update, run_animations);
     }
 }
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::{adaptor::AssetServerAdaptor, prelude::*};
+    use bevy::prelude::*;
+
+    /// a headless app with the plugin
+    pub(crate) fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            bevy::asset::AssetPlugin::default(),
+            HuiPlugin,
+        ));
+        app.init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_asset::<TextureAtlasLayout>();
+        app
+    }
+
+    /// parses a template into the assets
+    pub(crate) fn add_template(app: &mut App, html: &str) -> Handle<HtmlTemplate> {
+        let server = app.world().resource::<AssetServer>().clone();
+        let (_, template) = parse_template::<nom::error::VerboseError<&[u8]>>(
+            html.as_bytes(),
+            &mut AssetServerAdaptor { server: &server },
+        )
+        .expect("valid template");
+        app.world_mut()
+            .resource_mut::<Assets<HtmlTemplate>>()
+            .add(template)
+    }
+
+    /// binds a custom node tag to a template
+    pub(crate) fn add_component(app: &mut App, name: &str, html: &str) {
+        let handle = add_template(app, html);
+        app.world_mut()
+            .resource_mut::<ComponentBindings>()
+            .register(name, move |mut cmd| {
+                cmd.insert(HtmlNode(handle.clone()));
+            });
+    }
+
+    /// builds a template with properties
+    pub(crate) fn spawn_template(app: &mut App, html: &str, props: TemplateProperties) -> Entity {
+        let handle = add_template(app, html);
+        spawn_handle(app, handle, props)
+    }
+
+    /// builds a loaded template with properties
+    pub(crate) fn spawn_handle(
+        app: &mut App,
+        handle: Handle<HtmlTemplate>,
+        props: TemplateProperties,
+    ) -> Entity {
+        let entity = app.world_mut().spawn((HtmlNode(handle), props)).id();
+        app.update();
+        app.update();
+        entity
+    }
+
+    /// the nodes with an `UiId`
+    pub(crate) fn find(app: &mut App, id: &str) -> Vec<Entity> {
+        app.world_mut()
+            .query::<(Entity, &UiId)>()
+            .iter(app.world())
+            .filter(|(_, ui_id)| ui_id.id() == id)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// the text of a node
+    pub(crate) fn text(app: &App, entity: Entity) -> String {
+        app.world()
+            .get::<Text>(entity)
+            .map(|text| text.0.clone())
+            .unwrap_or_default()
+    }
+}
@@ -1,8 +1,14 @@
-use crate::{adaptor::LoadContextAdaptor, data::HtmlTemplate, error::ParseError, parse::parse_template};
+use crate::{
+    adaptor::LoadContextAdaptor,
+    data::HtmlTemplate,
+    error::ParseError,
+    parse::{parse_class_rules, parse_template},
+};
 use bevy::{
-    asset::{io::Reader, AssetLoader},
+    asset::{io::Reader, AssetLoader, AssetPath},
     prelude::*,
 };
+use serde::{Deserialize, Serialize};
 
 pub struct LoaderPlugin;
 impl Plugin for LoaderPlugin {
@@ -12,17 +18,24 @@ impl Plugin for LoaderPlugin {
     }
 }
 
+/// the templates extending the one that is loaded,
+/// set on the base template to detect `extends` cycles.
+#[derive(Default, Serialize, Deserialize)]
+pub struct HtmlLoaderSettings {
+    extended_by: Vec<String>,
+}
+
 #[derive(Default)]
 pub struct HtmlAssetLoader;
 impl AssetLoader for HtmlAssetLoader {
     type Asset = HtmlTemplate;
-    type Settings = ();
+    type Settings = HtmlLoaderSettings;
     type Error = ParseError;
 
     async fn load<'a>(
         &self,
         reader: &mut dyn Reader,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
         load_context: &mut bevy::asset::LoadContext<'a>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
@@ -31,19 +44,106 @@ impl AssetLoader for HtmlAssetLoader {
             .await
             .map_err(|err| ParseError::FailedToRead(err.to_string()))?;
         let mut adapter = LoadContextAdaptor { load_context };
-        match parse_template::<crate::error::VerboseHtmlError>(&bytes, &mut adapter) {
-            Ok((_, template)) => Ok(template),
+        let mut template = match parse_template::<crate::error::VerboseHtmlError>(&bytes, &mut adapter) {
+            Ok((_, template)) => template,
             Err(err) => match err {
-                nom::Err::Incomplete(_) => Err(ParseError::Incomplete),
+                nom::Err::Incomplete(_) => return Err(ParseError::Incomplete),
                 nom::Err::Error(err) | nom::Err::Failure(err) => {
                     let file_path = load_context.path().to_str().unwrap_or_default();
-                    Err(ParseError::Nom(err.format(&bytes, &file_path)))
+                    return Err(ParseError::Nom(err.format(&bytes, file_path)));
                 }
             },
+        };
+
+        // shared classes come first, the
+        // templates own classes extend them
+        let mut classes = vec![];
+        for path in template.style_imports.iter() {
+            let bytes = load_context
+                .read_asset_bytes(AssetPath::from(path.clone()))
+                .await
+                .map_err(|err| ParseError::FailedToRead(err.to_string()))?;
+            let mut adapter = LoadContextAdaptor { load_context };
+            match parse_class_rules::<crate::error::VerboseHtmlError>(&bytes, &mut adapter) {
+                Ok((_, rules)) => classes.extend(rules),
+                Err(err) => match err {
+                    nom::Err::Incomplete(_) => return Err(ParseError::Incomplete),
+                    nom::Err::Error(err) | nom::Err::Failure(err) => {
+                        return Err(ParseError::Nom(err.format(&bytes, path)));
+                    }
+                },
+            }
         }
+        let local = std::mem::take(&mut template.classes);
+        template.add_classes(classes);
+        template.add_classes(local);
+
+        let Some(extends) = template.extends.clone() else {
+            return Ok(template);
+        };
+
+        let (base_path, chain) =
+            resolve_base(load_context.asset_path(), &extends, &settings.extended_by)?;
+
+        // loaded as dependency, changes to the
+        // base reload this template aswell
+        let base = load_context
+            .loader()
+            .with_settings(move |settings: &mut HtmlLoaderSettings| {
+                settings.extended_by = chain.clone();
+            })
+            .immediate()
+            .load::<HtmlTemplate>(base_path)
+            .await
+            .map_err(|err| ParseError::Extends(extends, err.to_string()))?;
+
+        Ok(template.merge_into(base.take()))
     }
 
     fn extensions(&self) -> &[&str] {
         &["html", "xml"]
     }
 }
+
+/// the path of the base template, relative to the extending one
+/// or the asset root with a leading `/`. Fails if the base already
+/// extends the template, returns the chain of extending templates.
+fn resolve_base(
+    path: &AssetPath,
+    extends: &str,
+    extended_by: &[String],
+) -> Result<(AssetPath<'static>, Vec<String>), ParseError> {
+    let base_path = path
+        .resolve_embed(extends)
+        .map_err(|err| ParseError::Extends(extends.to_string(), err.to_string()))?;
+
+    let mut chain = extended_by.to_vec();
+    chain.push(path.to_string());
+    if chain.contains(&base_path.to_string()) {
+        chain.push(base_path.to_string());
+        return Err(ParseError::ExtendsCycle(chain.join(" -> ")));
+    }
+
+    Ok((base_path, chain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("settings.html", "base.html", &[] => Ok("base.html".to_string()); "same folder")]
+    #[test_case("menus/settings.html", "base.html", &[] => Ok("menus/base.html".to_string()); "subfolder")]
+    #[test_case("menus/settings.html", "../base.html", &[] => Ok("base.html".to_string()); "parent folder")]
+    #[test_case("menus/settings.html", "/screens/base.html", &[] => Ok("screens/base.html".to_string()); "asset root")]
+    #[test_case("base.html", "base.html", &[] => Err("base.html -> base.html".to_string()); "extends itself")]
+    #[test_case("b.html", "a.html", &["a.html"] => Err("a.html -> b.html -> a.html".to_string()); "cycle")]
+    fn test_resolve_base(path: &str, extends: &str, extended_by: &[&str]) -> Result<String, String> {
+        let extended_by = extended_by.iter().map(|path| path.to_string()).collect::<Vec<_>>();
+        match resolve_base(&AssetPath::parse(path), extends, &extended_by) {
+            Ok((base, _)) => Ok(base.to_string()),
+            Err(ParseError::ExtendsCycle(chain)) => Err(chain),
+            Err(err) => panic!("{err}"),
+        }
+    }
+}
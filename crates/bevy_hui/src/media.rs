@@ -0,0 +1,258 @@
+use crate::{
+    data::{HtmlTemplate, StyleAttr, XNode},
+    styles::RegisteredStates,
+};
+use bevy::{platform::collections::HashSet, prelude::*, ui::ComputedUiRenderTargetInfo};
+
+pub struct MediaPlugin;
+impl Plugin for MediaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HuiBreakpoints>();
+        app.register_type::<HuiBreakpoints>();
+        app.register_type::<UiMedia>();
+        app.init_resource::<RegisteredStates>();
+        app.add_systems(
+            Update,
+            (
+                update_media.before(crate::stylesheet::restyle_nodes),
+                check_prefixes,
+                check_states.run_if(resource_changed::<HuiBreakpoints>),
+            ),
+        );
+    }
+}
+
+/// Named minimum viewport widths in logical pixels, used as
+/// style prefixes. `md:width="50%"` applies from 768px upwards.
+///
+/// `portrait:` and `landscape:` are always available.
+///
+/// `app.insert_resource(HuiBreakpoints::default().with("tablet", 900.))`
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect]
+pub struct HuiBreakpoints(pub Vec<(String, f32)>);
+
+impl Default for HuiBreakpoints {
+    fn default() -> Self {
+        Self(vec![
+            ("sm".into(), 640.),
+            ("md".into(), 768.),
+            ("lg".into(), 1024.),
+            ("xl".into(), 1280.),
+            ("2xl".into(), 1536.),
+        ])
+    }
+}
+
+impl HuiBreakpoints {
+    /// adds or replaces a breakpoint.
+    pub fn with(mut self, name: &str, min_width: f32) -> Self {
+        match self.0.iter_mut().find(|(bp, _)| bp == name) {
+            Some(breakpoint) => breakpoint.1 = min_width,
+            None => self.0.push((name.to_string(), min_width)),
+        }
+        self
+    }
+
+    /// the prefixes active for a viewport size, by
+    /// ascending width followed by the orientation.
+    pub fn active(&self, size: Vec2) -> Vec<String> {
+        let orientation = match size.x.partial_cmp(&size.y) {
+            Some(std::cmp::Ordering::Less) => Some("portrait"),
+            Some(std::cmp::Ordering::Greater) => Some("landscape"),
+            _ => None,
+        };
+
+        let mut active = self
+            .0
+            .iter()
+            .filter(|(_, min_width)| size.x >= *min_width)
+            .collect::<Vec<_>>();
+        active.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        active
+            .into_iter()
+            .map(|(name, _)| name.clone())
+            .chain(orientation.map(String::from))
+            .collect()
+    }
+
+    /// if the prefix is a breakpoint or an orientation.
+    pub fn contains(&self, prefix: &str) -> bool {
+        matches!(prefix, "portrait" | "landscape") || self.0.iter().any(|(name, _)| name == prefix)
+    }
+}
+
+/// the media prefixes matching the viewport of
+/// the node's camera, larger breakpoints last.
+#[derive(Component, Debug, Clone, Default, PartialEq, Deref, Reflect)]
+#[reflect]
+pub struct UiMedia(Vec<String>);
+
+impl UiMedia {
+    /// unwraps a style with a matching media prefix,
+    /// other styles are kept as they are.
+    pub(crate) fn resolve(&self, style: StyleAttr) -> StyleAttr {
+        match style {
+            StyleAttr::Prefixed(prefix, style) if self.contains(&prefix) => *style,
+            style => style,
+        }
+    }
+
+    /// `resolve` for a list of styles. Matching media styles are
+    /// moved after the others, the larger breakpoint wins.
+    pub(crate) fn resolve_all(
+        &self,
+        styles: impl IntoIterator<Item = StyleAttr>,
+    ) -> Vec<StyleAttr> {
+        let mut ranked = styles
+            .into_iter()
+            .map(|style| (self.rank(&style), self.resolve(style)))
+            .collect::<Vec<_>>();
+        ranked.sort_by_key(|(rank, _)| *rank);
+        ranked.into_iter().map(|(_, style)| style).collect()
+    }
+
+    /// the order a style is applied in, `0` for
+    /// styles without a matching media prefix.
+    pub(crate) fn rank(&self, style: &StyleAttr) -> usize {
+        match style {
+            StyleAttr::Prefixed(prefix, _) => self
+                .iter()
+                .position(|active| active == prefix)
+                .map_or(0, |position| position + 1),
+            _ => 0,
+        }
+    }
+}
+
+fn update_media(
+    breakpoints: Res<HuiBreakpoints>,
+    mut nodes: Query<(Ref<ComputedUiRenderTargetInfo>, &mut UiMedia)>,
+) {
+    nodes.iter_mut().for_each(|(target, mut media)| {
+        if !target.is_changed() && !breakpoints.is_changed() {
+            return;
+        }
+        media.set_if_neq(UiMedia(breakpoints.active(target.logical_size())));
+    });
+}
+
+/// rejects registered states named like a breakpoint,
+/// the breakpoints may be inserted after the states.
+fn check_states(breakpoints: Res<HuiBreakpoints>, mut states: ResMut<RegisteredStates>) {
+    states.0.retain(|state| {
+        let clashes = breakpoints.contains(state);
+        if clashes {
+            warn!("`{state}` is a breakpoint and cannot be registered as a state");
+        }
+        !clashes
+    });
+}
+
+/// warns once per loaded template about style prefixes, that
+/// are neither a state nor a breakpoint, like a typo `mdd:`.
+fn check_prefixes(
+    mut events: MessageReader<AssetEvent<HtmlTemplate>>,
+    templates: Res<Assets<HtmlTemplate>>,
+    breakpoints: Res<HuiBreakpoints>,
+    states: Res<RegisteredStates>,
+    server: Res<AssetServer>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        let Some(template) = templates.get(*id) else {
+            continue;
+        };
+
+        let mut prefixes = HashSet::new();
+        template
+            .classes
+            .values()
+            .flatten()
+            .chain(template.root.iter().flat_map(node_styles))
+            .for_each(|style| {
+                if let StyleAttr::Prefixed(prefix, _) = style {
+                    prefixes.insert(prefix.clone());
+                }
+            });
+        template
+            .root
+            .iter()
+            .for_each(|node| uncompiled_prefixes(node, &mut prefixes));
+
+        let name = template
+            .name
+            .clone()
+            .or_else(|| server.get_path(*id).map(|path| path.to_string()))
+            .unwrap_or_else(|| "template".to_string());
+
+        prefixes
+            .iter()
+            .filter(|prefix| {
+                !matches!(prefix.as_str(), "hover" | "pressed" | "active")
+                    && !breakpoints.contains(prefix)
+                    && !states.contains(prefix)
+            })
+            .for_each(|prefix| {
+                warn!("`{name}` uses the unknown style prefix `{prefix}:`, it is neither a breakpoint nor a registered state");
+            });
+    }
+}
+
+/// the static styles of a node and its descendants
+fn node_styles(node: &XNode) -> Box<dyn Iterator<Item = &StyleAttr> + '_> {
+    Box::new(
+        node.styles
+            .iter()
+            .chain(node.children.iter().flat_map(node_styles)),
+    )
+}
+
+fn uncompiled_prefixes(node: &XNode, prefixes: &mut HashSet<String>) {
+    prefixes.extend(
+        node.uncompiled
+            .iter()
+            .filter_map(|attr| attr.prefix.clone()),
+    );
+    node.children
+        .iter()
+        .for_each(|child| uncompiled_prefixes(child, prefixes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_larger_breakpoint_wins() {
+        let media = UiMedia(HuiBreakpoints::default().active(Vec2::new(1000., 600.)));
+        let styles = media.resolve_all([
+            StyleAttr::Prefixed("md".into(), Box::new(StyleAttr::Width(Val::Percent(50.)))),
+            StyleAttr::Prefixed("sm".into(), Box::new(StyleAttr::Width(Val::Percent(100.)))),
+            StyleAttr::Prefixed("xl".into(), Box::new(StyleAttr::Width(Val::Px(10.)))),
+            StyleAttr::Height(Val::Px(20.)),
+        ]);
+
+        let width = styles.iter().rev().find_map(|style| match style {
+            StyleAttr::Width(val) => Some(*val),
+            _ => None,
+        });
+        assert_eq!(width, Some(Val::Percent(50.)));
+        assert!(matches!(styles[0], StyleAttr::Prefixed(..)));
+        assert!(matches!(styles[1], StyleAttr::Height(_)));
+    }
+
+    #[test]
+    fn test_active_by_width() {
+        let breakpoints = HuiBreakpoints(vec![("lg".into(), 1024.), ("sm".into(), 640.)]);
+        assert_eq!(
+            breakpoints.active(Vec2::new(1200., 800.)),
+            vec!["sm", "lg", "landscape"]
+        );
+    }
+}
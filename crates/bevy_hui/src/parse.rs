@@ -1,7 +1,16 @@
 use crate::adaptor::AssetLoadAdaptor;
 use crate::animation::{AnimationDirection, Atlas};
-use crate::data::{Action, AttrTokens, Attribute, FontReference, HtmlTemplate, StyleAttr, XNode};
+use crate::data::{
+    Action, AttrTokens, Attribute, FontReference, HtmlTemplate, PropertyDef, PropertyKind,
+    PropertyTransition, Repeat, StyleAttr, XNode,
+};
+use crate::expr::{
+    parse_encoded_expression, parse_expression, parse_interpolation, parse_segments, Expr,
+    Interpolation, InterpolationPart, Segment,
+};
 use crate::prelude::NodeType;
+use crate::stylesheet::{Combinator, CompoundSelector, Selector, SelectorPart, StyleRule};
+use crate::theme::THEME_PREFIX;
 use crate::util::SlotMap;
 use bevy::math::{Rect, UVec2, Vec2};
 use bevy::platform::collections::HashMap;
@@ -21,9 +30,9 @@ use bevy::{
 };
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, take_until, take_while, take_while1, take_while_m_n},
-    character::complete::{char, multispace0},
-    combinator::{complete, map, map_parser, not, rest},
+    bytes::complete::{tag, take_until, take_while, take_while1, take_while_m_n},
+    character::complete::{char, multispace0, multispace1},
+    combinator::{complete, cut, map, map_parser, not, opt, rest},
     error::{context, ContextError, ErrorKind, ParseError},
     multi::{many0, separated_list1},
     number::complete::float,
@@ -58,7 +67,14 @@ where
 
     let (_, mut xml) = parse_xml_node(input)?;
 
+    let extends = xml
+        .attributes
+        .iter()
+        .find_map(|attr| (attr.key == b"extends").then(|| decode_entities(attr.value, false)));
+
     let mut name = None;
+    let mut style_imports = vec![];
+    let mut classes = HashMap::<String, Vec<StyleAttr>>::default();
     let mut properties = HashMap::default();
     let mut root = vec![];
     let mut content = SlotMap::<String>::default();
@@ -66,24 +82,32 @@ where
     for child in xml.children.drain(..) {
         match child.name {
             b"property" => {
-                if let (Some(key), Some(value)) = (
-                    child
-                        .attributes
-                        .iter()
-                        .find_map(|attr| (attr.key == b"name").then_some(attr.value)),
-                    child.value,
-                ) {
-                    let str_key = String::from_utf8_lossy(key).to_string();
-                    let str_val = String::from_utf8_lossy(value).to_string();
-                    properties.insert(str_key, str_val);
+                if let (_, Some((key, def))) = parse_property_def(&child)? {
+                    properties.insert(key, def);
                 };
             }
             b"name" => {
                 if let Some(content) = child.value {
-                    let str_name = String::from_utf8_lossy(content).to_string();
+                    let str_name = decode_entities(content, false);
                     name = Some(str_name);
                 };
             }
+            b"style" => {
+                if let Some(src) = child
+                    .attributes
+                    .iter()
+                    .find_map(|attr| (attr.key == b"src").then_some(attr.value))
+                {
+                    style_imports.push(decode_entities(src, false));
+                }
+
+                if let Some(rules) = child.value {
+                    let (_, rules) = parse_class_rules(rules, loader)?;
+                    rules.into_iter().for_each(|(class, styles)| {
+                        classes.entry(class).or_default().extend(styles);
+                    });
+                }
+            }
             _ => {
                 let (_, node) = from_raw_xml::<E>(child, &mut content, loader)?;
                 root.push(node);
@@ -91,10 +115,17 @@ where
         }
     }
 
+    let mut uuid = 0;
+    root.iter_mut()
+        .for_each(|node| assign_uuids(node, &mut uuid));
+
     Ok((
         "".as_bytes(),
         HtmlTemplate {
             name,
+            extends,
+            style_imports,
+            classes,
             properties,
             root,
             content,
@@ -102,6 +133,91 @@ where
     ))
 }
 
+/// a declared property,
+/// `<property name="hp" type="float" required="true" min="0">100</property>`
+fn parse_property_def<'a, E>(xml: &Xml<'a>) -> IResult<&'a [u8], Option<(String, PropertyDef)>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let mut key = None;
+    let mut def = PropertyDef {
+        default: xml.value.map(|value| decode_entities(value, false)),
+        ..Default::default()
+    };
+
+    for attr in xml.attributes.iter() {
+        match attr.key {
+            b"name" => key = Some(String::from_utf8_lossy(attr.value).to_string()),
+            b"type" => {
+                let (_, kind) = context(
+                    "unknown property type, try `string` `float` `int` `bool` `color` `val` `list` `entity`",
+                    parse_property_kind,
+                )(attr.value)?;
+                def.kind = kind;
+            }
+            b"required" => {
+                let (_, required) = cut(parse_bool)(attr.value)?;
+                def.required = required;
+            }
+            b"min" => {
+                let (_, min) = context("`min` expects a number", cut(parse_float))(attr.value)?;
+                def.min = Some(min);
+            }
+            b"max" => {
+                let (_, max) = context("`max` expects a number", cut(parse_float))(attr.value)?;
+                def.max = Some(max);
+            }
+            _ => (),
+        }
+    }
+
+    if let (Some(key), Some(value), Some(default)) = (&key, xml.value, &def.default) {
+        if def.validate(key, &default.as_str().into()).is_err() {
+            let err = E::from_error_kind(value, ErrorKind::Verify);
+            return Err(nom::Err::Failure(E::add_context(
+                value,
+                "the default value does not match the declared property",
+                err,
+            )));
+        }
+    }
+
+    Ok((b"", key.map(|key| (key, def))))
+}
+
+fn parse_property_kind<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], PropertyKind, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let kind = match input {
+        b"string" => PropertyKind::String,
+        b"float" => PropertyKind::Float,
+        b"int" => PropertyKind::Int,
+        b"bool" => PropertyKind::Bool,
+        b"color" => PropertyKind::Color,
+        b"val" => PropertyKind::Val,
+        b"list" => PropertyKind::List,
+        b"entity" => PropertyKind::Entity,
+        _ => {
+            return Err(nom::Err::Failure(E::from_error_kind(
+                input,
+                ErrorKind::NoneOf,
+            )))
+        }
+    };
+    Ok((b"", kind))
+}
+
+/// gives every node a template unique id,
+/// used to find the node again after build.
+pub(crate) fn assign_uuids(node: &mut XNode, next: &mut u64) {
+    *next += 1;
+    node.uuid = *next;
+    node.children
+        .iter_mut()
+        .for_each(|child| assign_uuids(child, next));
+}
+
 fn trim_comments0<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Vec<&'a [u8]>, E>
 where
     E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
@@ -132,20 +248,76 @@ where
     let (_, node_type) = parse_node_type(xml.name)?;
     xnode.node_type = node_type;
 
-    xnode.content_id = xml
-        .value
-        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
-        .map(|raw| content_map.insert(raw))
-        .unwrap_or_default();
+    // text and spans keep every text run, a run after
+    // a child node becomes a span of its own
+    let mut spans = vec![];
+    match xnode.node_type {
+        NodeType::Text | NodeType::Span => {
+            let is_root = matches!(xnode.node_type, NodeType::Text);
+            let runs = xml.text.len();
+            let mut content: Option<String> = None;
+            for (index, (position, raw)) in xml.text.iter().enumerate() {
+                let decoded = decode_text::<E>(raw)?;
+                let first = is_root && index == 0 && *position == 0;
+                let last = is_root && index + 1 == runs && *position == xml.children.len();
+                let Some(text) = normalize_text(&decoded, first, last) else {
+                    continue;
+                };
+
+                match (*position, content.as_mut()) {
+                    (0, Some(content)) => content.push_str(&text),
+                    (0, None) => content = Some(text),
+                    _ => spans.push((
+                        *position,
+                        XNode {
+                            node_type: NodeType::Span,
+                            content_id: content_map.insert(text),
+                            ..Default::default()
+                        },
+                    )),
+                }
+            }
+
+            xnode.content_id = content
+                .map(|text| content_map.insert(text))
+                .unwrap_or_default();
+        }
+        _ => {
+            xnode.content_id = match xml.value {
+                Some(raw) => content_map.insert(decode_text::<E>(raw)?),
+                None => Default::default(),
+            };
+        }
+    }
 
     for attr in xml.attributes.iter() {
         let (_input, compiled_attr) = match xnode.node_type {
+            NodeType::Slot | NodeType::Block if attr.key == b"name" && attr.prefix.is_none() => {
+                let (_, name) = as_string(attr.value)?;
+                (attr.key, Attribute::Name(name))
+            }
             NodeType::Custom(_) => {
                 match attribute_from_parts::<E>(attr.prefix, attr.key, attr.value, loader) {
                     Ok(attr) => attr,
                     Err(_) => as_prop(attr.key, attr.value)?,
                 }
             }
+            // slot attributes are passed to the slotted content,
+            // the slot itself is replaced and cannot be styled
+            NodeType::Slot => {
+                match attribute_from_parts::<E>(attr.prefix, attr.key, attr.value, loader) {
+                    Ok((_, Attribute::Style(_))) => {
+                        let err = E::from_error_kind(attr.key, ErrorKind::Verify);
+                        return Err(nom::Err::Failure(E::add_context(
+                            attr.key,
+                            "a `<slot>` is replaced by its content and cannot be styled",
+                            err,
+                        )));
+                    }
+                    Ok(attr) => attr,
+                    Err(_) => as_prop(attr.key, attr.value)?,
+                }
+            }
             _ => attribute_from_parts(attr.prefix, attr.key, attr.value, loader)?,
         };
 
@@ -164,21 +336,80 @@ where
                 xnode.tags.insert(key, val);
             }
             Attribute::Watch(watch_id) => xnode.watch = Some(watch_id),
+            Attribute::Condition(condition) => xnode.condition = Some(condition),
+            Attribute::Repeat(repeat) => xnode.repeat = Some(repeat),
+            Attribute::Slot(slot) => xnode.slot = Some(slot),
+            Attribute::Class(classes) => xnode.classes.extend(classes),
         }
     }
 
-    for child in xml.children.drain(..) {
+    let mut spans = spans.into_iter().peekable();
+    for (position, child) in xml.children.drain(..).enumerate() {
+        while let Some((_, span)) = spans.next_if(|(at, _)| *at == position) {
+            xnode.children.push(span);
+        }
         let (_, node) = from_raw_xml(child, content_map, loader)?;
         xnode.children.push(node);
     }
+    xnode.children.extend(spans.map(|(_, span)| span));
 
     Ok(("".as_bytes(), xnode))
 }
 
+/// decodes a text run and checks its expressions.
+fn decode_text<'a, E>(raw: &'a [u8]) -> Result<String, nom::Err<E>>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let decoded = decode_entities(raw, true);
+    if raw == decoded.as_bytes() {
+        validate_expressions(raw)?;
+    } else if validate_expressions::<nom::error::Error<&[u8]>>(decoded.as_bytes()).is_err() {
+        // decoded text is owned, point at the raw value instead
+        let err = E::from_error_kind(raw, ErrorKind::Verify);
+        return Err(nom::Err::Failure(E::add_context(
+            raw,
+            "invalid expression",
+            err,
+        )));
+    }
+    Ok(decoded)
+}
+
+/// whitespace at the edges of a text run is removed, if it
+/// contains a line break or starts/ends the whole text.
+/// runs that end up empty are dropped.
+fn normalize_text(text: &str, first: bool, last: bool) -> Option<String> {
+    let is_space = |c: char| c.is_ascii_whitespace();
+    let body = text.trim_matches(is_space);
+    if body.is_empty() {
+        return (!text.is_empty() && !text.contains('\n') && !first && !last)
+            .then(|| text.to_string());
+    }
+
+    let start = text.len() - text.trim_start_matches(is_space).len();
+    let end = text.trim_end_matches(is_space).len();
+    let (leading, trailing) = (&text[..start], &text[end..]);
+
+    let mut out = String::with_capacity(text.len());
+    if !first && !leading.contains('\n') {
+        out.push_str(leading);
+    }
+    out.push_str(body);
+    if !last && !trailing.contains('\n') {
+        out.push_str(trailing);
+    }
+    Some(out)
+}
+
 struct Xml<'a> {
     prefix: Option<&'a [u8]>,
     name: &'a [u8],
+    /// the first text run, that is not blank
     value: Option<&'a [u8]>,
+    /// every text run, with the count of child
+    /// nodes before it
+    text: Vec<(usize, &'a [u8])>,
     attributes: Vec<XmlAttr<'a>>,
     children: Vec<Xml<'a>>,
 }
@@ -231,18 +462,42 @@ where
                 name: start_name,
                 attributes,
                 value: None,
+                text: vec![],
                 children: vec![],
             },
         ));
     }
 
-    let (input, children) = many0(parse_xml_node)(input)?;
+    // mixed content, text runs between child nodes
+    let mut input = input;
+    let mut children = vec![];
+    let mut text = vec![];
+    loop {
+        let (rest, run) = take_while(|b: u8| b != b'<')(input)?;
+        if !run.is_empty() {
+            text.push((children.len(), run));
+        }
 
-    let (input, _) = trim_comments0(input)?;
+        input = rest;
+        if input.is_empty() || input.starts_with(b"</") {
+            break;
+        }
 
-    let (input, value) = map(take_while(|b: u8| b != b'<'), |c: &[u8]| {
-        (c.len() > 0).then_some(c)
-    })(input)?;
+        if let Ok((rest, _)) = parse_comment::<E>(input) {
+            input = rest;
+            continue;
+        }
+
+        let (rest, child) = parse_xml_node(input)?;
+        children.push(child);
+        input = rest;
+    }
+
+    let value = text
+        .iter()
+        .map(|(_, run)| *run)
+        .find(|run| !run.trim_ascii().is_empty())
+        .or(text.first().map(|(_, run)| *run));
 
     let (input, (end_prefix, end_name)) = parse_xml_end(input)?;
     if start_name != end_name || prefix != end_prefix {
@@ -260,6 +515,7 @@ where
             name: start_name,
             attributes,
             value,
+            text,
             children,
         },
     ))
@@ -282,13 +538,81 @@ where
     many0(map(
         tuple((
             preceded(multispace0, parse_prefix0),
-            terminated(take_snake, tag("=")),
-            delimited(tag("\""), is_not("\""), tag("\"")),
+            terminated(take_snake, delimited(multispace0, tag("="), multispace0)),
+            parse_xml_attr_value,
         )),
         |(prefix, key, value)| XmlAttr { prefix, key, value },
     ))(input)
 }
 
+/// single or double quoted, can be empty.
+/// Use the other quote or `&quot;` inside the value.
+fn parse_xml_attr_value<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    alt((
+        delimited(tag("\""), take_while(|b: u8| b != b'"'), tag("\"")),
+        delimited(tag("'"), take_while(|b: u8| b != b'\''), tag("'")),
+    ))(input)
+}
+
+/// decodes entities like `&lt;` and character references
+/// like `&#x2764;`. Unknown entities are kept as they are.
+/// With `escape_braces`, decoded braces are escaped as `{{`, `}}`
+/// so they do not start an expression.
+pub(crate) fn decode_entities(input: &[u8], escape_braces: bool) -> String {
+    let input = String::from_utf8_lossy(input);
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input.as_ref();
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest
+            .find(';')
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+
+        match decoded {
+            Some((c @ ('{' | '}'), end)) if escape_braces => {
+                out.push(c);
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = match name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse::<u32>().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
 fn parse_node_type<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], NodeType, E>
 where
     E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
@@ -298,7 +622,10 @@ where
         map(tag("image"), |_| NodeType::Image),
         map(tag("button"), |_| NodeType::Button),
         map(tag("text"), |_| NodeType::Text),
+        map(tag("span"), |_| NodeType::Span),
         map(tag("slot"), |_| NodeType::Slot),
+        map(tag("fragment"), |_| NodeType::Fragment),
+        map(tag("block"), |_| NodeType::Block),
         map(tag("template"), |_| NodeType::Template),
         map(rest, |val| {
             let custom = String::from_utf8_lossy(val).to_string();
@@ -307,22 +634,71 @@ where
     ))(input)
 }
 
-fn parse_uncompiled<'a>(
+/// checks every `{...}` expression in text content.
+fn validate_expressions<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], (), E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (_, segments) = parse_segments(input)?;
+    for segment in segments.iter() {
+        if let Segment::Expression(expr) = segment {
+            context("invalid expression", parse_expression)(expr)?;
+        }
+    }
+    Ok((input, ()))
+}
+
+/// the source of a value that is a single `{...}` expression.
+fn parse_single_expression<'a, E>(value: &'a [u8]) -> IResult<&'a [u8], Option<&'a [u8]>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (rest, segments) = parse_segments(value)?;
+    match (rest, segments.as_slice()) {
+        (b"", [Segment::Expression(source)]) => Ok((rest, Some(source))),
+        _ => Ok((value, None)),
+    }
+}
+
+/// `$primary`, short for `{theme.primary}`
+fn parse_theme_token(value: &[u8]) -> Option<Interpolation> {
+    let token = value.strip_prefix(b"$")?;
+    if token.is_empty() || !token.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_') {
+        return None;
+    }
+
+    Some(Interpolation(vec![InterpolationPart::Expr(Expr::Var(
+        format!("{THEME_PREFIX}{}", String::from_utf8_lossy(token)),
+    ))]))
+}
+
+fn parse_uncompiled<'a, E>(
     prefix: Option<&'a [u8]>,
     key: &'a [u8],
     value: &'a [u8],
-) -> Option<Attribute> {
-    let result: IResult<&[u8], &[u8]> = delimited(tag("{"), is_not("}"), tag("}"))(value);
-    match result {
-        Ok((_, prop)) => {
-            return Some(Attribute::Uncompiled(AttrTokens {
-                prefix: prefix.map(|p| String::from_utf8_lossy(p).to_string()),
-                ident: String::from_utf8_lossy(key).to_string(),
-                key: String::from_utf8_lossy(prop).to_string(),
-            }));
+) -> IResult<&'a [u8], Option<Attribute>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let interpolation = match parse_theme_token(value) {
+        Some(token) => token,
+        None => {
+            let (_, Some(interpolation)) = parse_interpolation(value)? else {
+                return Ok((value, None));
+            };
+            interpolation
         }
-        Err(_) => None,
-    }
+    };
+
+    Ok((
+        value,
+        Some(Attribute::Uncompiled(AttrTokens {
+            prefix: prefix.map(|p| String::from_utf8_lossy(p).to_string()),
+            ident: String::from_utf8_lossy(key).to_string(),
+            key: String::from_utf8_lossy(value).to_string(),
+            value: interpolation,
+        })),
+    ))
 }
 
 pub(crate) fn as_prop<'a, E>(key: &'a [u8], value: &'a [u8]) -> IResult<&'a [u8], Attribute, E>
@@ -343,7 +719,26 @@ pub(crate) fn attribute_from_parts<'a, 'b, 'c, E>(
 where
     E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
 {
-    if let Some(attr) = parse_uncompiled(prefix, key, value) {
+    if key == b"if" && prefix.is_none() {
+        let (_, Some(condition)) = parse_single_expression(value)? else {
+            let err = E::from_error_kind(value, ErrorKind::Tag);
+            return Err(nom::Err::Failure(E::add_context(
+                value,
+                "`if` expects an expression like `{show}` or `{count > 0}`",
+                err,
+            )));
+        };
+        parse_encoded_expression(condition)?;
+        let condition = decode_entities(condition, false);
+        return Ok((b"", Attribute::Condition(condition.trim().to_string())));
+    }
+
+    if key == b"for" && prefix.is_none() {
+        let (_, repeat) = context("`for` expects a loop like `item in {items}`", parse_repeat)(value)?;
+        return Ok((b"", Attribute::Repeat(repeat)));
+    }
+
+    if let (_, Some(attr)) = parse_uncompiled(prefix, key, value)? {
         return Ok((b"", attr));
     }
 
@@ -366,6 +761,15 @@ where
             let (_, val) = as_string(value)?;
             Ok((key, Attribute::Target(val)))
         }
+        b"slot" => {
+            let (_, val) = as_string(value)?;
+            Ok((key, Attribute::Slot(val)))
+        }
+        b"class" => {
+            let (_, val) = as_string(value)?;
+            let classes = val.split_whitespace().map(|class| class.to_string()).collect();
+            Ok((key, Attribute::Class(classes)))
+        }
         b"src" => {
             let (_, val) = as_string(value)?;
             Ok((key, Attribute::Path(val)))
@@ -397,6 +801,242 @@ where
     }
 }
 
+/// class names with their styles, in declaration order
+pub(crate) type ClassRules = Vec<(String, Vec<StyleAttr>)>;
+
+/// parses the class rules of a `<style>` block or a shared class file.
+/// `.primary { background: #333; hover:background: #555; }`
+pub(crate) fn parse_class_rules<'a, E>(
+    input: &'a [u8],
+    loader: &mut impl AssetLoadAdaptor,
+) -> IResult<&'a [u8], ClassRules, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let mut input = input;
+    let mut rules = vec![];
+    loop {
+        let (rest, _) = many0(alt((multispace1, parse_css_comment)))(input)?;
+        if rest.is_empty() {
+            return Ok((rest, rules));
+        }
+
+        let (rest, (class, body)) = context(
+            "expected a class rule like `.name { background: #FFF; }`",
+            tuple((
+                delimited(tag("."), take_class_name, multispace0),
+                parse_rule_body,
+            )),
+        )(rest)?;
+
+        let styles = parse_declarations(body, loader)?;
+        rules.push((String::from_utf8_lossy(class).to_string(), styles));
+        input = rest;
+    }
+}
+
+/// parses a stylesheet, each rule can have a list of selectors.
+/// `node.card > text, #title { font_size: 20; }`
+pub(crate) fn parse_stylesheet<'a, E>(
+    input: &'a [u8],
+    loader: &mut impl AssetLoadAdaptor,
+) -> IResult<&'a [u8], Vec<StyleRule>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let mut input = input;
+    let mut rules = vec![];
+    loop {
+        let (rest, _) = many0(alt((multispace1, parse_css_comment)))(input)?;
+        if rest.is_empty() {
+            return Ok((rest, rules));
+        }
+
+        let (rest, (selectors, body)) = context(
+            "expected a rule like `node.card > text { font_size: 20; }`",
+            tuple((
+                separated_list1(delimited(multispace0, tag(","), multispace0), parse_selector),
+                preceded(multispace0, parse_rule_body),
+            )),
+        )(rest)?;
+
+        let styles = parse_declarations(body, loader)?;
+        rules.extend(selectors.into_iter().map(|selector| StyleRule {
+            selector,
+            styles: styles.clone(),
+        }));
+        input = rest;
+    }
+}
+
+/// compound selectors, joined by ` ` (descendant) or ` > ` (child)
+fn parse_selector<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Selector, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (mut input, compound) = parse_compound_selector(input)?;
+    let mut parts = vec![SelectorPart {
+        combinator: Combinator::Descendant,
+        compound,
+    }];
+
+    loop {
+        let combinator = alt((
+            delimited(multispace0, tag(">"), multispace0).map(|_| Combinator::Child),
+            multispace1.map(|_| Combinator::Descendant),
+        ));
+
+        match tuple((combinator, parse_compound_selector))(input) {
+            Ok((rest, (combinator, compound))) => {
+                parts.push(SelectorPart {
+                    combinator,
+                    compound,
+                });
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => return Ok((input, Selector(parts))),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// `button#start.primary`, `*` or `.large`
+fn parse_compound_selector<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], CompoundSelector, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (input, node_type) = opt(alt((tag("*"), take_class_name)))(input)?;
+    let (input, parts) = many0(alt((
+        preceded(tag("#"), take_class_name).map(|id| (true, id)),
+        preceded(tag("."), take_class_name).map(|class| (false, class)),
+    )))(input)?;
+
+    if node_type.is_none() && parts.is_empty() {
+        return Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Verify)));
+    }
+
+    let mut compound = CompoundSelector {
+        node_type: node_type
+            .filter(|node_type| *node_type != b"*")
+            .map(|node_type| String::from_utf8_lossy(node_type).to_string()),
+        ..Default::default()
+    };
+
+    parts.into_iter().for_each(|(is_id, name)| {
+        let name = String::from_utf8_lossy(name).to_string();
+        match is_id {
+            true => compound.id = Some(name),
+            false => compound.classes.push(name),
+        }
+    });
+
+    Ok((input, compound))
+}
+
+/// the `{ .. }` body of a rule
+fn parse_rule_body<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    delimited(tag("{"), take_while(|b: u8| b != b'}'), tag("}"))(input)
+}
+
+/// `;` separated declarations of a rule body
+fn parse_declarations<'a, E>(
+    body: &'a [u8],
+    loader: &mut impl AssetLoadAdaptor,
+) -> Result<Vec<StyleAttr>, nom::Err<E>>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let mut styles = vec![];
+    for declaration in body.split(|b| *b == b';') {
+        let declaration = declaration.trim_ascii();
+        if declaration.is_empty() {
+            continue;
+        }
+        styles.push(parse_class_declaration(declaration, loader)?);
+    }
+    Ok(styles)
+}
+
+/// a single `hover:background: #555` declaration
+fn parse_class_declaration<'a, E>(
+    input: &'a [u8],
+    loader: &mut impl AssetLoadAdaptor,
+) -> Result<StyleAttr, nom::Err<E>>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let separator = || preceded(multispace0, tag(":"));
+    let (value, (prefix, key)) = context(
+        "expected a declaration like `background: #FFF`",
+        alt((
+            tuple((
+                terminated(take_snake, tag(":")).map(Some),
+                terminated(take_snake, separator()),
+            )),
+            terminated(take_snake, separator()).map(|key| (None, key)),
+        )),
+    )(input)?;
+
+    match attribute_from_parts::<E>(prefix, key, value.trim_ascii(), loader)? {
+        (_, Attribute::Style(style)) => Ok(style),
+        _ => {
+            let err = E::from_error_kind(input, ErrorKind::Verify);
+            Err(nom::Err::Failure(E::add_context(
+                input,
+                "rules can only contain style attributes",
+                err,
+            )))
+        }
+    }
+}
+
+fn parse_css_comment<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    delimited(tag("/*"), take_until("*/"), tag("*/"))(input)
+}
+
+fn take_class_name<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    take_while1(|b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')(input)
+}
+
+fn parse_repeat<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Repeat, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    let (input, (item, index, _, key)) = tuple((
+        preceded(multispace0, take_snake),
+        opt(preceded(
+            delimited(multispace0, tag(","), multispace0),
+            take_snake,
+        )),
+        delimited(multispace1, tag("in"), multispace1),
+        delimited(
+            tag("{"),
+            delimited(multispace0, take_snake, multispace0),
+            tag("}"),
+        ),
+    ))(input)?;
+
+    Ok((
+        input,
+        Repeat {
+            item: String::from_utf8_lossy(item).to_string(),
+            index: index
+                .map(|index| String::from_utf8_lossy(index).to_string())
+                .unwrap_or_else(|| "index".to_string()),
+            key: String::from_utf8_lossy(key).to_string(),
+        },
+    ))
+}
+
 #[rustfmt::skip]
 fn parse_style<'a, E>(
     prefix: Option<&'a [u8]>,
@@ -430,6 +1070,9 @@ where
         b"min_height" => map(parse_val, StyleAttr::MinHeight)(value)?,
         b"min_width" => map(parse_val, StyleAttr::MinWidth)(value)?,
         b"delay" => map(parse_delay, StyleAttr::Delay)(value)?,
+        b"leave_delay" => map(parse_delay, StyleAttr::LeaveDelay)(value)?,
+        b"transition" => map(parse_transition, StyleAttr::Transition)(value)?,
+        b"animate_changes" => map(parse_bool, StyleAttr::AnimateChanges)(value)?,
         b"ease" => map(parse_easing, StyleAttr::Easing)(value)?,
         b"image_color" => map(parse_color, StyleAttr::ImageColor)(value)?,
         b"image_region" => map(parse_rect, StyleAttr::ImageRegion)(value)?,
@@ -497,11 +1140,21 @@ where
         }
     };
 
+    Ok((input, prefixed_style(prefix, style)))
+}
+
+/// wraps a style in its conditional prefix,
+/// `hover:`, `pressed:`, `active:` or any other.
+pub(crate) fn prefixed_style(prefix: Option<&[u8]>, style: StyleAttr) -> StyleAttr {
     match prefix {
-        Some(b"pressed") => Ok((input, StyleAttr::Pressed(Box::new(style)))),
-        Some(b"hover") => Ok((input, StyleAttr::Hover(Box::new(style)))),
-        Some(b"active") => Ok((input, StyleAttr::Active(Box::new(style)))),
-        _ => Ok((input, style)),
+        Some(b"pressed") => StyleAttr::Pressed(Box::new(style)),
+        Some(b"hover") => StyleAttr::Hover(Box::new(style)),
+        Some(b"active") => StyleAttr::Active(Box::new(style)),
+        Some(prefix) => StyleAttr::Prefixed(
+            String::from_utf8_lossy(prefix).to_string(),
+            Box::new(style),
+        ),
+        None => style,
     }
 }
 
@@ -793,11 +1446,18 @@ where
     )(input)
 }
 
+/// a static value, with decoded entities and
+/// the escaped braces `{{`, `}}` resolved.
 fn as_string<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], String, E>
 where
     E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
 {
-    map(rest, |v| String::from_utf8_lossy(v).to_string())(input)
+    map(rest, |v: &[u8]| {
+        let unescaped = String::from_utf8_lossy(v)
+            .replace("{{", "{")
+            .replace("}}", "}");
+        decode_entities(unescaped.as_bytes(), false)
+    })(input)
 }
 
 fn as_string_list<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Vec<String>, E>
@@ -1471,7 +2131,7 @@ where
 }
 
 /// 10px 10%
-fn parse_val<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Val, E>
+pub(crate) fn parse_val<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Val, E>
 where
     E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
 {
@@ -1517,6 +2177,37 @@ where
     )(input)
 }
 
+/// `background 200ms cubic_out, width 1s`
+fn parse_transition<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Vec<PropertyTransition>, E>
+where
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+{
+    context(
+        "Is not a valid transition, try `background 200ms cubic_out, width 1s`",
+        separated_list1(
+            delimited(multispace0, tag(","), multispace0),
+            map(
+                tuple((
+                    preceded(multispace0, take_snake),
+                    preceded(multispace1, parse_delay),
+                    opt(preceded(
+                        multispace1,
+                        map_parser(
+                            take_while1(|b: u8| b.is_ascii_alphabetic() || b == b'_'),
+                            parse_easing,
+                        ),
+                    )),
+                )),
+                |(property, duration, easing)| PropertyTransition {
+                    property: String::from_utf8_lossy(property).to_string(),
+                    duration,
+                    easing,
+                },
+            ),
+        ),
+    )(input)
+}
+
 #[cfg(feature = "picking")]
 fn parse_pickable<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], (bool, bool), E>
 where
@@ -1537,7 +2228,7 @@ where
 // #000000
 // #FFF
 #[rustfmt::skip]
-fn parse_color<'a,E>(input: &'a [u8]) -> IResult<&'a [u8], Color,E>
+pub(crate) fn parse_color<'a,E>(input: &'a [u8]) -> IResult<&'a [u8], Color,E>
 where
     E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
 {
@@ -1773,7 +2464,9 @@ where
 mod tests {
     use super::*;
     use crate::error::VerboseHtmlError;
-    use nom::error::VerboseError;
+    use crate::error::PropertyError;
+    use crate::prelude::{PropertyValue, TemplateProperties};
+    use nom::error::{VerboseError, VerboseErrorKind};
     use test_case::test_case;
 
     #[test_case("#FFFFFFFF", Color::WHITE)]
@@ -1869,6 +2562,7 @@ mod tests {
 
     #[test_case(r#"    pressed:background="fsdfsf"  pressed:background="fsdfsf"  <!-- test -->    pressed:background="fsdfsf" \n"#)]
     #[test_case(r#"pressed:background="fsdfsf"#)]
+    #[test_case(r#"width = "10px""#)]
     fn test_parse_xml_attr(input: &str) {
         let (_, _attr) = parse_xml_attr::<nom::error::Error<_>>(input.as_bytes())
             .map_err(|err| err.map_input(|i| std::str::from_utf8(i).unwrap()))
@@ -1886,6 +2580,49 @@ mod tests {
         // dbg!(&attr);
     }
 
+    #[test_case(r#"id='say "hi"'"# => "say \"hi\""; "single quotes")]
+    #[test_case("tag:foo=''" => ""; "empty")]
+    #[test_case(r#"id = "a""# => "a"; "whitespace")]
+    #[test_case(r#"id="a &amp; b &lt;3""# => "a & b <3"; "entities")]
+    #[test_case(r#"id="{{not an expression}}""# => "{not an expression}"; "escaped braces")]
+    #[test_case(r#"src="&#123;&#123;x&#125;""# => "{{x}"; "encoded braces")]
+    fn test_decode_xml_attr(input: &str) -> String {
+        let (_, attributes) = parse_xml_attr::<VerboseError<_>>(input.as_bytes()).unwrap();
+        let [attr] = attributes.as_slice() else {
+            panic!("expected one attribute in `{input}`");
+        };
+        match attribute_from_parts::<VerboseError<_>>(
+            attr.prefix,
+            attr.key,
+            attr.value,
+            &mut DummyLoaderAdapter,
+        ) {
+            Ok((_, Attribute::Id(value) | Attribute::Path(value) | Attribute::Tag(_, value))) => {
+                value
+            }
+            other => panic!("unexpected attribute {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_decode_xml_attrs() {
+        let (_, attributes) =
+            parse_xml_attr::<VerboseError<_>>(b"tag:foo=\"\" id=\"a\"").unwrap();
+        let pairs = attributes
+            .iter()
+            .map(|attr| (attr.key, attr.value))
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(&b"foo"[..], &b""[..]), (&b"id"[..], &b"a"[..])]);
+    }
+
+    #[test_case("a &lt; b &amp;&amp; c", false => "a < b && c"; "named")]
+    #[test_case("&#x2764; &#65;", false => "\u{2764} A"; "character references")]
+    #[test_case("&unknown; & ;", false => "&unknown; & ;"; "unknown")]
+    #[test_case("&#123;x&#125;", true => "{{x}}"; "escaped braces")]
+    fn test_decode_entities(input: &str, escape_braces: bool) -> String {
+        decode_entities(input.as_bytes(), escape_braces)
+    }
+
     #[test_case(r#"<node pressed:background="rgb(1,1,1)" active="hello"><text p:hello="sdf">hello</text></node>"#)]
     #[test_case(r#"<slot/>"#)]
     #[test_case(r#"<node pressed:background="rgba(1,1,1,0)" active="hello" />"#)]
@@ -1910,18 +2647,21 @@ mod tests {
         };
     }
 
+    struct DummyLoaderAdapter;
+    impl AssetLoadAdaptor for DummyLoaderAdapter {
+        fn load<'a, A: bevy::asset::Asset>(
+            &mut self,
+            _path: impl Into<bevy::asset::AssetPath<'a>>,
+        ) -> bevy::asset::Handle<A> {
+            bevy::asset::Handle::default()
+        }
+    }
+
     #[test_case("../../example/assets/demo/menu.html")]
     #[test_case("../../example/assets/demo/panel.html")]
     #[test_case("../../example/assets/demo/button.html")]
     #[test_case("../../example/assets/demo/card.html")]
     fn test_parse_template_full(file_path: &str) {
-        use bevy::asset::{Asset, AssetPath, Handle};
-        struct DummyLoaderAdapter;
-        impl AssetLoadAdaptor for DummyLoaderAdapter {
-            fn load<'a, A: Asset>(&mut self, _path: impl Into<AssetPath<'a>>) -> Handle<A> {
-                Handle::default()
-            }
-        }
         let input = std::fs::read_to_string(file_path).unwrap();
         match parse_template::<nom::error::VerboseError<_>>(
             input.as_bytes(),
@@ -1936,6 +2676,391 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_parse_multiple_roots() {
+        let input = r#"
+        <template>
+            <node></node>
+            <fragment>
+                <text>a</text>
+                <text>b</text>
+            </fragment>
+        </template>
+        "#;
+        let (_, template) =
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        assert_eq!(template.root.len(), 2);
+        assert!(matches!(template.root[1].node_type, NodeType::Fragment));
+        assert_eq!(template.root[1].children.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_mixed_content() {
+        let input = r##"
+        <template>
+            <text font_size="20">Hello <span font_color="#F00">{name}</span>!</text>
+        </template>
+        "##;
+        let (_, template) =
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        let text = &template.root[0];
+        let content = |node: &XNode| template.content.get(node.content_id).cloned();
+
+        assert!(matches!(text.node_type, NodeType::Text));
+        assert_eq!(content(text).as_deref(), Some("Hello "));
+        assert_eq!(text.children.len(), 2);
+        assert!(matches!(text.children[0].node_type, NodeType::Span));
+        assert!(matches!(
+            text.children[0].styles.as_slice(),
+            [StyleAttr::FontColor(_)]
+        ));
+        assert_eq!(content(&text.children[0]).as_deref(), Some("{name}"));
+        assert!(matches!(text.children[1].node_type, NodeType::Span));
+        assert_eq!(content(&text.children[1]).as_deref(), Some("!"));
+    }
+
+    #[test]
+    fn test_parse_property_defs() {
+        let input = r#"
+        <template>
+            <property name="title">Untitled</property>
+            <property name="hp" type="float" required="true" min="0" max="100"/>
+            <property name="color" type="color">#FFF</property>
+            <node/>
+        </template>
+        "#;
+        let (_, template) =
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+
+        assert_eq!(
+            template.properties.get("title"),
+            Some(&PropertyDef {
+                default: Some("Untitled".into()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            template.properties.get("hp"),
+            Some(&PropertyDef {
+                default: None,
+                kind: PropertyKind::Float,
+                required: true,
+                min: Some(0.),
+                max: Some(100.),
+            })
+        );
+        assert_eq!(
+            template.properties.get("color").map(|def| def.kind),
+            Some(PropertyKind::Color)
+        );
+
+        let errors = |props: TemplateProperties| template.validate_properties(&props);
+        assert_eq!(
+            errors(TemplateProperties::default()),
+            vec![PropertyError::Missing("hp".into())]
+        );
+        assert_eq!(
+            errors(TemplateProperties::default().with("hp", "50").with("color", "red")),
+            vec![PropertyError::Type(
+                "color".into(),
+                PropertyKind::Color,
+                "red".into()
+            )]
+        );
+        assert_eq!(
+            errors(TemplateProperties::default().with_typed("hp", 120.)),
+            vec![PropertyError::Max("hp".into(), 120., 100.)]
+        );
+        assert!(errors(
+            TemplateProperties::default()
+                .with_typed("hp", 0.)
+                .with_typed("color", Color::WHITE)
+        )
+        .is_empty());
+    }
+
+    #[test_case(r#"<property name="hp" type="number"/>"#; "unknown type")]
+    #[test_case(r#"<property name="hp" min="low"/>"#; "invalid min")]
+    #[test_case(r#"<property name="hp" type="int">1.5</property>"#; "invalid default")]
+    fn test_parse_property_def_error(property: &str) {
+        let input = format!("<template>{property}<node/></template>");
+        assert!(
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).is_err()
+        );
+    }
+
+    #[test_case("<text>{maxx(1, 2)}</text>", "unknown function"; "text")]
+    #[test_case(r#"<node if="{abs(1, 2)}"/>"#, "function expects 1 argument"; "condition")]
+    #[test_case(r#"<node width="{clamp(w)}px"/>"#, "function `clamp` expects 3"; "attribute")]
+    fn test_parse_call_error(node: &str, message: &str) {
+        let input = format!("<template>\n<node>\n{node}\n</node>\n</template>");
+        let Err(nom::Err::Failure(err)) =
+            parse_template::<VerboseHtmlError>(input.as_bytes(), &mut DummyLoaderAdapter)
+        else {
+            panic!("`{node}` should fail");
+        };
+
+        // without colors
+        let formatted = err.format(input.as_bytes(), "test.html");
+        let mut plain = String::new();
+        let mut chars = formatted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\u{1b}' => _ = chars.by_ref().find(|c| *c == 'm'),
+                c => plain.push(c),
+            }
+        }
+        assert!(plain.contains(message), "{plain}");
+        assert!(plain.contains("in `test.html` at line 3"), "{plain}");
+    }
+
+    #[test]
+    fn test_parse_named_slots() {
+        let input = r#"
+        <template>
+            <node>
+                <slot name="header"><text>Untitled</text></slot>
+                <slot/>
+                <card>
+                    <text slot="header">Inventory</text>
+                </card>
+            </node>
+        </template>
+        "#;
+        let (_, template) =
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        let root = &template.root[0];
+
+        assert!(matches!(root.children[0].node_type, NodeType::Slot));
+        assert_eq!(root.children[0].name.as_deref(), Some("header"));
+        assert_eq!(root.children[0].children.len(), 1);
+        assert!(root.children[1].name.is_none());
+        assert_eq!(
+            root.children[2].children[0].slot.as_deref(),
+            Some("header")
+        );
+    }
+
+    #[test]
+    fn test_parse_scoped_slot() {
+        let input = r#"
+        <template>
+            <slot name="row" row="{current}" label="static"/>
+        </template>
+        "#;
+        let (_, template) =
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        let slot = &template.root[0];
+
+        assert_eq!(slot.name.as_deref(), Some("row"));
+        assert_eq!(slot.uncompiled.len(), 1);
+        assert_eq!(slot.uncompiled[0].ident, "row");
+        assert_eq!(slot.defs.get("label").map(|s| s.as_str()), Some("static"));
+        assert!(slot.styles.is_empty());
+    }
+
+    #[test_case(r#"<slot width="100%"/>"#; "style")]
+    #[test_case(r#"<slot hover:background="rgb(1,1,1)"/>"#; "prefixed style")]
+    fn test_parse_styled_slot(slot: &str) {
+        let input = format!("<template>{slot}</template>");
+        let result = parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter);
+        let Err(nom::Err::Failure(err)) = result else {
+            panic!("a styled slot should fail");
+        };
+        assert!(err.errors.iter().any(|(_, kind)| matches!(
+            kind,
+            VerboseErrorKind::Context("a `<slot>` is replaced by its content and cannot be styled")
+        )));
+    }
+
+    #[test]
+    fn test_merge_extended_template() {
+        let base = r#"
+        <template>
+            <property name="title">base</property>
+            <node>
+                <block name="title"><text>default title</text></block>
+                <block name="body"><text>default body</text></block>
+            </node>
+        </template>
+        "#;
+        let screen = r#"
+        <template extends="base_screen.html">
+            <property name="title">screen</property>
+            <block name="body">
+                <text>screen body</text>
+            </block>
+        </template>
+        "#;
+        let (_, base) =
+            parse_template::<VerboseError<_>>(base.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        let (_, screen) =
+            parse_template::<VerboseError<_>>(screen.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        assert_eq!(screen.extends.as_deref(), Some("base_screen.html"));
+
+        let merged = screen.merge_into(base);
+        let blocks = &merged.root[0].children;
+        let text = |block: &XNode| merged.content.get(block.children[0].content_id).cloned();
+
+        assert_eq!(
+            merged
+                .properties
+                .get("title")
+                .and_then(|def| def.default.as_deref()),
+            Some("screen")
+        );
+        assert!(matches!(blocks[0].node_type, NodeType::Block));
+        assert_eq!(text(&blocks[0]).as_deref(), Some("default title"));
+        assert_eq!(text(&blocks[1]).as_deref(), Some("screen body"));
+        assert!(merged.find_node(blocks[1].children[0].uuid).is_some());
+    }
+
+    #[test]
+    fn test_parse_classes() {
+        let input = r##"
+        <template>
+            <style>
+                /* shared button look */
+                .primary { background: #333; hover:background: #555; padding: 10px; }
+                .large { width: 200px; }
+                .primary { font_size: 20 }
+            </style>
+            <button class="primary large" width="100px"></button>
+        </template>
+        "##;
+        let (_, template) =
+            parse_template::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+        let button = &template.root[0];
+
+        assert_eq!(button.classes, vec!["primary", "large"]);
+        assert_eq!(template.classes.get("primary").map(|c| c.len()), Some(4));
+        assert!(matches!(
+            template.node_styles(button).as_slice(),
+            [
+                StyleAttr::Background(_),
+                StyleAttr::Hover(_),
+                StyleAttr::Padding(_),
+                StyleAttr::FontSize(_),
+                StyleAttr::Width(Val::Px(200.)),
+                StyleAttr::Width(Val::Px(100.)),
+            ]
+        ));
+    }
+
+    #[test_case("md", "width", "50%" => Some("md".to_string()); "breakpoint")]
+    #[test_case("portrait", "flex_direction", "column" => Some("portrait".to_string()); "orientation")]
+    #[test_case("selected", "background", "#F00" => Some("selected".to_string()); "custom state")]
+    #[test_case("hover", "width", "50%" => None; "hover")]
+    fn test_parse_prefixed_style(prefix: &str, key: &str, value: &str) -> Option<String> {
+        match attribute_from_parts::<VerboseError<_>>(
+            Some(prefix.as_bytes()),
+            key.as_bytes(),
+            value.as_bytes(),
+            &mut DummyLoaderAdapter,
+        ) {
+            Ok((_, Attribute::Style(StyleAttr::Prefixed(prefix, _)))) => Some(prefix),
+            _ => None,
+        }
+    }
+
+    #[test_case("background 200ms cubic_out" => Some(vec![("background".to_string(), 0.2, Some(EaseFunction::CubicOut))]); "single")]
+    #[test_case("background 200ms cubic_out, width 1s" => Some(vec![("background".to_string(), 0.2, Some(EaseFunction::CubicOut)), ("width".to_string(), 1., None)]); "list")]
+    #[test_case("width 0.5 ,  height 2s elastic_out" => Some(vec![("width".to_string(), 0.5, None), ("height".to_string(), 2., Some(EaseFunction::ElasticOut))]); "spacing")]
+    #[test_case("background" => None; "missing duration")]
+    fn test_parse_transition(input: &str) -> Option<Vec<(String, f32, Option<EaseFunction>)>> {
+        parse_transition::<VerboseError<_>>(input.as_bytes())
+            .map(|(_, transitions)| {
+                transitions
+                    .into_iter()
+                    .map(|t| (t.property, t.duration, t.easing))
+                    .collect()
+            })
+            .ok()
+    }
+
+    #[test_case("leave_delay", "300ms" => true; "leave delay")]
+    #[test_case("transition", "width 1s" => true; "transition")]
+    #[test_case("transition", "1s width" => false; "swapped transition")]
+    #[test_case("animate_changes", "true" => true; "animate changes")]
+    fn test_parse_timing(key: &str, value: &str) -> bool {
+        attribute_from_parts::<VerboseError<_>>(
+            None,
+            key.as_bytes(),
+            value.as_bytes(),
+            &mut DummyLoaderAdapter,
+        )
+        .is_ok_and(|(_, attr)| {
+            matches!(
+                attr,
+                Attribute::Style(
+                    StyleAttr::LeaveDelay(_)
+                        | StyleAttr::Transition(_)
+                        | StyleAttr::AnimateChanges(_)
+                )
+            )
+        })
+    }
+
+    #[test_case(".a { width: 10px; }" => true; "single rule")]
+    #[test_case(".a{width:10px}.b{pressed:height:5%;}" => true; "compact")]
+    #[test_case(".a { width: {w}; }" => false; "expression")]
+    #[test_case(".a { not_a_style: 1; }" => false; "unknown style")]
+    #[test_case(".a { width: 10px; " => false; "unclosed rule")]
+    #[test_case("a { width: 10px; }" => false; "missing dot")]
+    fn test_parse_class_rules(input: &str) -> bool {
+        parse_class_rules::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).is_ok()
+    }
+
+    #[test]
+    fn test_parse_stylesheet() {
+        let input = r##"
+        /* every title */
+        node.card > text#title, button.primary.large {
+            font_size: 20;
+            hover:font_color: #FFF;
+        }
+        * { padding: 2px; }
+        "##;
+        let (_, rules) =
+            parse_stylesheet::<VerboseError<_>>(input.as_bytes(), &mut DummyLoaderAdapter).unwrap();
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].styles.len(), 2);
+        assert_eq!(
+            rules[0].selector,
+            Selector(vec![
+                SelectorPart {
+                    combinator: Combinator::Descendant,
+                    compound: CompoundSelector {
+                        node_type: Some("node".into()),
+                        id: None,
+                        classes: vec!["card".into()],
+                    },
+                },
+                SelectorPart {
+                    combinator: Combinator::Child,
+                    compound: CompoundSelector {
+                        node_type: Some("text".into()),
+                        id: Some("title".into()),
+                        classes: vec![],
+                    },
+                },
+            ])
+        );
+        assert_eq!(rules[1].selector.specificity(), (0, 2, 1));
+        assert_eq!(rules[2].selector.0[0].compound, CompoundSelector::default());
+    }
+
+    #[test_case("\n  hello\n  ", true, true => Some("hello".to_string()); "whole text")]
+    #[test_case("hello ", true, false => Some("hello ".to_string()); "before span")]
+    #[test_case(" and ", false, false => Some(" and ".to_string()); "between spans")]
+    #[test_case("\n    and\n    ", false, false => Some("and".to_string()); "line breaks")]
+    #[test_case(" ", false, false => Some(" ".to_string()); "single space")]
+    #[test_case("\n    ", false, false => None; "blank line")]
+    fn test_normalize_text(input: &str, first: bool, last: bool) -> Option<String> {
+        normalize_text(input, first, last)
+    }
+
     #[test_case(r#"hover:background="{color}""#)]
     #[test_case(r#"pressed:width="10%""#)]
     #[test_case(r#"active:height="10vw""#)]
@@ -1951,6 +3076,116 @@ mod tests {
         }
     }
 
+    #[test_case("{show}" => Some("show".to_string()); "property")]
+    #[test_case("{ !show }" => Some("!show".to_string()); "negated property")]
+    #[test_case("show" => None; "missing braces")]
+    #[test_case("{a &lt; b &amp;&amp; show}" => Some("a < b && show".to_string()); "entities")]
+    #[test_case("{a &lt;}" => None; "invalid decoded")]
+    fn test_parse_condition(input: &str) -> Option<String> {
+        match attribute_from_parts::<VerboseError<_>>(
+            None,
+            b"if",
+            input.as_bytes(),
+            &mut DummyLoaderAdapter,
+        ) {
+            Ok((_, Attribute::Condition(condition))) => Some(condition),
+            _ => None,
+        }
+    }
+
+    #[test_case("item in {items}" => Some(("item".to_string(), "index".to_string(), "items".to_string())); "simple loop")]
+    #[test_case(" row in { rows } " => Some(("row".to_string(), "index".to_string(), "rows".to_string())); "whitespace")]
+    #[test_case("cell, col in {cells}" => Some(("cell".to_string(), "col".to_string(), "cells".to_string())); "named index")]
+    #[test_case("cell,col in {cells}" => Some(("cell".to_string(), "col".to_string(), "cells".to_string())); "named index no space")]
+    #[test_case("{items}" => None; "missing item")]
+    fn test_parse_repeat(input: &str) -> Option<(String, String, String)> {
+        match attribute_from_parts::<VerboseError<_>>(
+            None,
+            b"for",
+            input.as_bytes(),
+            &mut DummyLoaderAdapter,
+        ) {
+            Ok((_, Attribute::Repeat(repeat))) => Some((repeat.item, repeat.index, repeat.key)),
+            _ => None,
+        }
+    }
+
+    #[test_case("{progress * 100}" => true; "expression")]
+    #[test_case("{is_open ? '#FFF' : '#000'}" => true; "ternary")]
+    #[test_case("{pad}px 10px" => true; "mixed literal")]
+    #[test_case("icons/{name}.png" => true; "path")]
+    #[test_case("10px" => false; "no expression")]
+    #[test_case("{progress *}" => false; "invalid expression")]
+    #[test_case("{progress" => false; "unclosed brace")]
+    #[test_case("{theme.primary}" => true; "theme property")]
+    #[test_case("$primary" => true; "theme token")]
+    #[test_case("$" => false; "empty theme token")]
+    fn test_parse_uncompiled(input: &str) -> bool {
+        matches!(
+            attribute_from_parts::<VerboseError<_>>(
+                None,
+                b"width",
+                input.as_bytes(),
+                &mut DummyLoaderAdapter,
+            ),
+            Ok((_, Attribute::Uncompiled(_)))
+        )
+    }
+
+    #[test_case(None, "background", PropertyValue::Color(Color::LinearRgba(bevy::color::LinearRgba::RED)); "color")]
+    #[test_case(Some("hover"), "width", PropertyValue::Val(Val::Percent(50.)); "prefixed val")]
+    #[test_case(None, "padding", PropertyValue::Val(Val::Px(4.)); "rect")]
+    #[test_case(None, "font_size", PropertyValue::Number(12.); "number")]
+    #[test_case(None, "animate_changes", PropertyValue::Bool(true); "bool")]
+    fn test_compile_typed(prefix: Option<&str>, key: &str, value: PropertyValue) {
+        let props = TemplateProperties::default().with_typed("value", value.clone());
+        let (_, attr) = attribute_from_parts::<VerboseError<_>>(
+            prefix.map(|prefix| prefix.as_bytes()),
+            key.as_bytes(),
+            b"{value}",
+            &mut DummyLoaderAdapter,
+        )
+        .unwrap();
+        let Attribute::Uncompiled(tokens) = attr else {
+            panic!("not an expression");
+        };
+
+        // the typed and the parsed string style are equal
+        let typed = value.to_attribute(prefix, key).unwrap();
+        let parsed = attribute_from_parts::<VerboseError<_>>(
+            prefix.map(|prefix| prefix.as_bytes()),
+            key.as_bytes(),
+            value.to_string().as_bytes(),
+            &mut DummyLoaderAdapter,
+        )
+        .map(|(_, attr)| attr)
+        .unwrap();
+        let compiled = tokens.compile(&props, &mut DummyLoaderAdapter).unwrap();
+        assert_eq!(format!("{typed:?}"), format!("{compiled:?}"));
+        assert_eq!(format!("{parsed:?}"), format!("{compiled:?}"));
+    }
+
+    #[test_case("{a &lt; b ? 10 : 20}px" => Some(Val::Px(10.)); "decoded expression")]
+    #[test_case("{a &gt; b ? 10 : 20}px" => Some(Val::Px(20.)); "decoded comparison")]
+    #[test_case("{a &lt;}px" => None; "invalid decoded")]
+    fn test_compile_entities(input: &str) -> Option<Val> {
+        let props = TemplateProperties::default().with("a", "1").with("b", "2");
+        let (_, attr) = attribute_from_parts::<VerboseError<_>>(
+            None,
+            b"width",
+            input.as_bytes(),
+            &mut DummyLoaderAdapter,
+        )
+        .ok()?;
+        let Attribute::Uncompiled(tokens) = attr else {
+            return None;
+        };
+        match tokens.compile(&props, &mut DummyLoaderAdapter)? {
+            Attribute::Style(StyleAttr::Width(val)) => Some(val),
+            _ => None,
+        }
+    }
+
     #[test_case("10px" => Some(BorderRect::all(10.0)); "all sides")]
     #[test_case("1px 2px" => Some(BorderRect::axes(1.0, 2.0)); "axis")]
     #[test_case("1px 2px 3px 4px" => Some(BorderRect::from([4.0, 2.0, 1.0, 3.0])); "individual sides")]
@@ -0,0 +1,264 @@
+use crate::{
+    build::{spawn_ui, TemplateProperties},
+    compile::recompile_changed_properties,
+    value::PropertyValue,
+};
+use bevy::prelude::*;
+
+/// Writes a value as template properties, derive it with
+/// `#[derive(HuiProps)]` for structs.
+///
+/// ```ignore
+/// #[derive(HuiProps)]
+/// struct Player {
+///     name: String,
+///     hp: f32,
+///     #[hui(rename = "bar_color")]
+///     color: Color,
+///     #[hui(skip)]
+///     id: u64,
+/// }
+///
+/// #[derive(HuiProps)]
+/// struct Hud {
+///     player: Player, // `player.name`, `player.hp`, ..
+///     round: u32,
+/// }
+///
+/// let props = hud.to_props();
+/// ```
+pub trait HuiProps {
+    /// writes the value as `key`. Structs write every
+    /// field as `key.field`, or `field` for an empty key.
+    fn write_props(&self, key: &str, props: &mut TemplateProperties);
+
+    /// the properties of a struct
+    fn to_props(&self) -> TemplateProperties {
+        let mut props = TemplateProperties::default();
+        self.write_props("", &mut props);
+        props
+    }
+}
+
+macro_rules! impl_value_props {
+    ($($ty:ty),*) => {$(
+        impl HuiProps for $ty {
+            fn write_props(&self, key: &str, props: &mut TemplateProperties) {
+                props.set_typed(key, self.clone());
+            }
+        }
+    )*};
+}
+
+/// numbers are stored as `f32`. Integers beyond `2^24`, like
+/// large `u64`, `i64` or `usize` ids, and `f64` lose precision,
+/// write them as a `String` to keep every digit.
+macro_rules! impl_number_props {
+    ($($ty:ty),*) => {$(
+        impl HuiProps for $ty {
+            fn write_props(&self, key: &str, props: &mut TemplateProperties) {
+                props.set_typed(key, *self as f32);
+            }
+        }
+    )*};
+}
+
+impl_value_props!(String, f32, i32, bool, Color, Val, Entity, PropertyValue);
+impl_number_props!(u8, u16, u32, u64, usize, i8, i16, i64, isize, f64);
+
+impl HuiProps for str {
+    fn write_props(&self, key: &str, props: &mut TemplateProperties) {
+        props.set_typed(key, self);
+    }
+}
+
+impl<T: HuiProps + ?Sized> HuiProps for &T {
+    fn write_props(&self, key: &str, props: &mut TemplateProperties) {
+        (**self).write_props(key, props);
+    }
+}
+
+/// `None` removes the property, and
+/// the `key.field` properties of a struct
+impl<T: HuiProps> HuiProps for Option<T> {
+    fn write_props(&self, key: &str, props: &mut TemplateProperties) {
+        match self {
+            Some(value) => value.write_props(key, props),
+            None => props.retain(|prop, _| {
+                prop != key
+                    && !prop
+                        .strip_prefix(key)
+                        .is_some_and(|field| field.starts_with('.'))
+            }),
+        }
+    }
+}
+
+/// a list, usable in `for`
+impl<T: Clone + Into<PropertyValue>> HuiProps for Vec<T> {
+    fn write_props(&self, key: &str, props: &mut TemplateProperties) {
+        props.set_typed(key, self.clone());
+    }
+}
+
+/// Writes a struct to the `TemplateProperties` of a node.
+/// Every change to it is written to the properties,
+/// which recompiles the template. Register the type with
+/// `register_hui_props`.
+///
+/// The sync is one way, from the struct to the properties.
+/// Properties changed by other systems are not read back,
+/// the next change of the struct overwrites its keys.
+///
+/// ```ignore
+/// cmd.spawn((HtmlNode(server.load("hud.html")), UiSync(hud)));
+///
+/// fn damage(mut huds: Query<&mut UiSync<Hud>>) {
+///     huds.iter_mut().for_each(|mut hud| hud.player.hp -= 1.);
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Default, Deref, DerefMut)]
+pub struct UiSync<T: HuiProps + Send + Sync + 'static>(pub T);
+
+pub trait HuiPropsAppExt {
+    /// syncs `UiSync<T>` with the properties of its node.
+    ///
+    /// `app.register_hui_props::<Hud>()`
+    fn register_hui_props<T: HuiProps + Send + Sync + 'static>(&mut self) -> &mut Self;
+}
+
+impl HuiPropsAppExt for App {
+    fn register_hui_props<T: HuiProps + Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.add_systems(
+            Update,
+            sync_props::<T>
+                .before(spawn_ui)
+                .before(recompile_changed_properties),
+        )
+    }
+}
+
+/// writes changed structs to the properties, only
+/// marks them changed if any property is different.
+fn sync_props<T: HuiProps + Send + Sync + 'static>(
+    mut nodes: Query<(&UiSync<T>, &mut TemplateProperties), Changed<UiSync<T>>>,
+) {
+    nodes.iter_mut().for_each(|(sync, mut props)| {
+        let mut synced = props.clone();
+        sync.write_props("", &mut synced);
+        props.set_if_neq(synced);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(HuiProps, Default)]
+    struct Player {
+        name: String,
+        hp: f32,
+        #[hui(rename = "bar_color")]
+        color: Color,
+        #[hui(skip)]
+        #[allow(dead_code)]
+        id: u64,
+    }
+
+    #[derive(HuiProps, Default)]
+    struct Hud {
+        player: Player,
+        round: u32,
+        items: Vec<&'static str>,
+        target: Option<Entity>,
+        leader: Option<Player>,
+    }
+
+    fn hud() -> Hud {
+        Hud {
+            player: Player {
+                name: "Hans".into(),
+                hp: 0.5,
+                color: Color::WHITE,
+                id: 7,
+            },
+            round: 3,
+            items: vec!["sword", "shield"],
+            target: None,
+            leader: None,
+        }
+    }
+
+    #[test]
+    fn test_to_props() {
+        let props = hud().to_props();
+
+        assert_eq!(props.get("player.name"), Some(&PropertyValue::from("Hans")));
+        assert_eq!(props.get("player.hp"), Some(&PropertyValue::Number(0.5)));
+        assert_eq!(
+            props.get("player.bar_color"),
+            Some(&PropertyValue::Color(Color::WHITE))
+        );
+        assert_eq!(props.get("round"), Some(&PropertyValue::Number(3.)));
+        assert_eq!(
+            props.get("items"),
+            Some(&PropertyValue::from(vec!["sword", "shield"]))
+        );
+        assert!(props.get("player.id").is_none());
+        assert!(props.get("target").is_none());
+        assert_eq!(props.len(), 5);
+    }
+
+    #[test]
+    fn test_write_props_prefix() {
+        let mut props = TemplateProperties::default()
+            .with("target", "old")
+            .with("leaderboard", "top");
+        hud().player.write_props("left", &mut props);
+        Hud {
+            leader: Some(hud().player),
+            ..hud()
+        }
+        .write_props("", &mut props);
+        assert_eq!(props.get("left.name"), Some(&PropertyValue::from("Hans")));
+        assert_eq!(props.get("leader.name"), Some(&PropertyValue::from("Hans")));
+        assert!(props.get("target").is_none());
+
+        props.insert("leader".into(), "old".into());
+        hud().write_props("", &mut props);
+        assert!(props.get("leader").is_none());
+        assert!(props.get("leader.name").is_none());
+        assert!(props.get("leader.bar_color").is_none());
+        assert_eq!(props.get("leaderboard"), Some(&PropertyValue::from("top")));
+    }
+
+    #[test]
+    fn test_sync() {
+        let mut app = App::new();
+        app.register_hui_props::<Hud>();
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                TemplateProperties::default().with("title", "hud"),
+                UiSync(hud()),
+            ))
+            .id();
+        app.update();
+
+        let props = app.world().get::<TemplateProperties>(entity).unwrap();
+        assert_eq!(props.get("title"), Some(&PropertyValue::from("hud")));
+        assert_eq!(props.get("player.hp"), Some(&PropertyValue::Number(0.5)));
+
+        app.world_mut()
+            .get_mut::<UiSync<Hud>>(entity)
+            .unwrap()
+            .player
+            .hp = 0.25;
+        app.update();
+
+        let props = app.world().get::<TemplateProperties>(entity).unwrap();
+        assert_eq!(props.get("player.hp"), Some(&PropertyValue::Number(0.25)));
+    }
+}
@@ -1,10 +1,12 @@
 use crate::{
     animation::{AnimationDirection, Atlas},
     build::InteractionObverser,
-    data::{FontReference, StyleAttr},
+    data::{FontReference, HtmlTemplate, PropertyTransition, StyleAttr},
+    media::HuiBreakpoints,
 };
 use bevy::{
-    ecs::{query::QueryEntityError, system::SystemParam},
+    ecs::{query::QueryEntityError, system::{EntityCommands, SystemParam}},
+    platform::collections::HashSet,
     prelude::*,
     ui::widget::NodeImageMode,
 };
@@ -15,58 +17,256 @@ use std::time::Duration;
 pub struct TransitionPlugin;
 impl Plugin for TransitionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (continues_interaction_checking, update_node_style));
+        app.add_systems(
+            Update,
+            (
+                continues_interaction_checking,
+                continues_state_checking,
+                continues_style_transition,
+                cascade_text_styles.before(update_node_style),
+                update_node_style,
+            ),
+        );
         app.register_type::<PressedTimer>();
         app.register_type::<HoverTimer>();
+        app.register_type::<ActiveTimer>();
+        app.register_type::<StateTimers>();
         app.register_type::<InteractionTimer>();
         app.register_type::<ComputedStyle>();
         app.register_type::<HtmlStyle>();
+        app.register_type::<DeclaredText>();
+        app.register_type::<UiStates>();
+        app.register_type::<NodeStyles>();
+        app.register_type::<DynamicStyles>();
+        app.register_type::<UiClassList>();
+        app.register_type::<StyleTransition>();
     }
 }
 
-/// interpolation timer for
-/// transitions
+/// interpolation timer for transitions, the
+/// durations are given by a `TransitionTiming`
 #[derive(Component, Clone, Default, Reflect)]
 #[reflect]
 pub struct InteractionTimer {
-    elapsed: Duration,
+    progress: f32,
+    /// the progress of properties with their own `transition`
+    properties: Vec<(String, f32)>,
+    /// the duration of `forward` and `backward`
     max: Duration,
 }
 
+/// the timing of a transition, the `delay`, `leave_delay`, `ease`
+/// and `transition` of the node, overridden by those of the state.
+#[derive(Clone, Copy)]
+pub struct TransitionTiming<'a> {
+    /// seconds to transition in
+    pub enter: f32,
+    /// seconds to transition out
+    pub leave: f32,
+    pub easing: Option<EaseFunction>,
+    node: &'a [PropertyTransition],
+    state: &'a [PropertyTransition],
+}
+
+impl TransitionTiming<'_> {
+    /// the property transitions, the state overrides the node
+    pub fn transitions(&self) -> impl Iterator<Item = &PropertyTransition> {
+        self.state.iter().chain(
+            self.node
+                .iter()
+                .filter(|t| !self.state.iter().any(|s| s.property == t.property)),
+        )
+    }
+
+    pub fn property(&self, name: &str) -> Option<&PropertyTransition> {
+        self.transitions().find(|t| t.property == name)
+    }
+}
+
 /// add this component to enable
 /// all active styles
 #[derive(Component)]
 pub struct UiActive;
 
+/// the custom states of a node, see `register_hui_state`
+#[derive(Component, Default, Debug, Clone, Deref, Reflect)]
+#[reflect]
+pub struct UiStates(Vec<String>);
+
+/// the prefixes of `register_hui_state`
+#[derive(Resource, Default, Debug, Deref)]
+pub(crate) struct RegisteredStates(pub(crate) Vec<String>);
+
+pub trait HuiStateAppExt {
+    /// Binds a style prefix to a component. `selected:background="#F00"`
+    /// applies while the node has the `Selected` component.
+    ///
+    /// `app.register_hui_state::<Selected>("selected")`
+    ///
+    /// Builtin states and `HuiBreakpoints` names are rejected,
+    /// also if the breakpoints are inserted later on.
+    fn register_hui_state<C: Component>(&mut self, prefix: &str) -> &mut Self;
+}
+
+impl HuiStateAppExt for App {
+    fn register_hui_state<C: Component>(&mut self, prefix: &str) -> &mut Self {
+        if matches!(prefix, "hover" | "pressed" | "active") {
+            warn!("`{prefix}` is a builtin state and cannot be registered");
+            return self;
+        }
+
+        if self
+            .world_mut()
+            .get_resource_or_init::<HuiBreakpoints>()
+            .contains(prefix)
+        {
+            warn!("`{prefix}` is a breakpoint and cannot be registered as a state");
+            return self;
+        }
+
+        self.world_mut()
+            .get_resource_or_init::<RegisteredStates>()
+            .0
+            .push(prefix.to_string());
+
+        let added = prefix.to_string();
+        let removed = prefix.to_string();
+        self.add_observer(
+            move |trigger: On<Add, C>, registered: Res<RegisteredStates>, mut cmd: Commands| {
+                // rejected after registration
+                if !registered.contains(&added) {
+                    return;
+                }
+
+                let state = added.clone();
+                cmd.entity(trigger.entity)
+                    .entry::<UiStates>()
+                    .or_default()
+                    .and_modify(move |mut states| {
+                        if !states.contains(&state) {
+                            states.0.push(state);
+                        }
+                    });
+            },
+        );
+        self.add_observer(move |trigger: On<Remove, C>, mut states: Query<&mut UiStates>| {
+            _ = states.get_mut(trigger.entity).map(|mut states| {
+                states.0.retain(|state| *state != removed);
+            });
+        });
+        self
+    }
+}
+
 impl InteractionTimer {
+    #[deprecated(note = "durations come from the style, use `InteractionTimer::default`")]
     pub fn new(max: Duration) -> Self {
-        Self {
-            elapsed: Duration::ZERO,
-            max,
-        }
+        Self { max, ..default() }
     }
 
+    /// the progress of the styles without
+    /// a property transition
     pub fn fraction(&self) -> f32 {
-        self.elapsed.div_duration_f32(self.max)
+        self.progress
+    }
+
+    /// the progress of a style
+    pub fn progress(&self, attr: &StyleAttr, timing: &TransitionTiming) -> f32 {
+        attr.property_name()
+            .and_then(|name| timing.property(name))
+            .and_then(|transition| self.property_progress(&transition.property))
+            .unwrap_or(self.progress)
+    }
+
+    /// the eased progress of a style
+    pub fn ratio(&self, attr: &StyleAttr, timing: &TransitionTiming) -> f32 {
+        let easing = attr
+            .property_name()
+            .and_then(|name| timing.property(name))
+            .and_then(|transition| transition.easing)
+            .or(timing.easing);
+        ease(easing, self.progress(attr, timing))
     }
 
+    /// `true` once every style transitioned in
+    pub fn is_finished(&self) -> bool {
+        self.progress >= 1. && self.properties.iter().all(|(_, p)| *p >= 1.)
+    }
+
+    #[deprecated(note = "use `tick` with the `TransitionTiming` of the style")]
     pub fn forward(&mut self, delta: Duration) {
-        self.elapsed = self
-            .elapsed
-            .checked_add(delta)
-            .map(|d| d.min(self.max))
-            .unwrap_or(self.elapsed);
+        self.progress = step(self.progress, delta, self.max.as_secs_f32(), true);
     }
 
+    #[deprecated(note = "use `tick` with the `TransitionTiming` of the style")]
     pub fn backward(&mut self, delta: Duration) {
-        self.elapsed = self.elapsed.checked_sub(delta).unwrap_or(Duration::ZERO);
+        self.progress = step(self.progress, delta, self.max.as_secs_f32(), false);
+    }
+
+    /// forward while `active`, backward otherwise
+    pub fn tick(&mut self, active: bool, delta: Duration, timing: &TransitionTiming) {
+        let duration = match active {
+            true => timing.enter,
+            false => timing.leave,
+        };
+        // new properties start where the shared progress was
+        self.step_properties(delta, timing, active);
+        self.progress = step(self.progress, delta, duration, active);
+    }
+
+    fn property_progress(&self, property: &str) -> Option<f32> {
+        self.properties
+            .iter()
+            .find(|(name, _)| name == property)
+            .map(|(_, progress)| *progress)
+    }
+
+    fn step_properties(&mut self, delta: Duration, timing: &TransitionTiming, forward: bool) {
+        for transition in timing.transitions() {
+            let index = match self
+                .properties
+                .iter()
+                .position(|(name, _)| *name == transition.property)
+            {
+                Some(index) => index,
+                None => {
+                    // starts where the shared progress is
+                    self.properties
+                        .push((transition.property.clone(), self.progress));
+                    self.properties.len() - 1
+                }
+            };
+
+            let progress = &mut self.properties[index].1;
+            *progress = step(*progress, delta, transition.duration, forward);
+        }
+    }
+}
+
+/// advances a progress by a duration in seconds,
+/// zero durations jump to the end
+fn step(progress: f32, delta: Duration, duration: f32, forward: bool) -> f32 {
+    let delta = match duration > 0. {
+        true => delta.as_secs_f32() / duration,
+        false => 1.,
+    };
+
+    match forward {
+        true => (progress + delta).min(1.),
+        false => (progress - delta).max(0.),
     }
 }
 
+/// applies the easing to a transition ratio
+fn ease(easing: Option<EaseFunction>, ratio: f32) -> f32 {
+    easing
+        .and_then(|ease| EasingCurve::new(0., 1., ease).sample(ratio))
+        .unwrap_or(ratio)
+}
+
 fn continues_interaction_checking(
     interactions: Query<(Entity, &Interaction), With<HtmlStyle>>,
-    mut hovers: Query<&mut HoverTimer>,
-    mut presseds: Query<&mut PressedTimer>,
+    mut timers: Query<(&HtmlStyle, &mut HoverTimer, &mut PressedTimer)>,
     observer: Query<&InteractionObverser>,
     time: Res<Time<Real>>,
 ) {
@@ -77,50 +277,63 @@ fn continues_interaction_checking(
             .unwrap_or_default()
             .chain(std::iter::once(&entity));
 
-        match interaction {
-            Interaction::Pressed => {
-                // ++ pressed ++ hover
-                subs.for_each(|sub| {
-                    if let (Ok(mut htimer), Ok(mut ptimer)) =
-                        (hovers.get_mut(*sub), presseds.get_mut(*sub))
-                    {
-                        ptimer.forward(time.delta());
-                        htimer.forward(time.delta());
-                    } else {
-                        warn!("non interacting node obsering `{sub}`")
-                    }
-                });
-            }
-            Interaction::Hovered => {
-                // ++ hover -- pressed
-                subs.for_each(|sub| {
-                    if let (Ok(mut htimer), Ok(mut ptimer)) =
-                        (hovers.get_mut(*sub), presseds.get_mut(*sub))
-                    {
-                        ptimer.backward(time.delta());
-                        htimer.forward(time.delta());
-                    } else {
-                        warn!("non interacting node obsering `{sub}`")
-                    }
-                });
-            }
-            Interaction::None => {
-                // -- hover --pressed
-                subs.for_each(|sub| {
-                    if let (Ok(mut htimer), Ok(mut ptimer)) =
-                        (hovers.get_mut(*sub), presseds.get_mut(*sub))
-                    {
-                        ptimer.backward(time.delta());
-                        htimer.backward(time.delta());
-                    } else {
-                        warn!("non interacting node obsering `{sub}`")
-                    }
-                });
-            }
+        let (hovered, pressed) = match interaction {
+            Interaction::Pressed => (true, true),
+            Interaction::Hovered => (true, false),
+            Interaction::None => (false, false),
         };
+
+        subs.for_each(|sub| {
+            let Ok((style, mut htimer, mut ptimer)) = timers.get_mut(*sub) else {
+                warn!("non interacting node obsering `{sub}`");
+                return;
+            };
+
+            htimer.tick(hovered, time.delta(), &style.timing(&style.hover));
+            ptimer.tick(pressed, time.delta(), &style.timing(&style.pressed));
+        });
     });
 }
 
+type StatefulNode<'a> = (
+    &'a HtmlStyle,
+    Has<UiActive>,
+    Option<&'a UiStates>,
+    &'a mut ActiveTimer,
+    &'a mut StateTimers,
+);
+
+/// runs the active and custom state timers,
+/// forward while the state is active
+fn continues_state_checking(
+    mut nodes: Query<StatefulNode>,
+    time: Res<Time<Real>>,
+) {
+    nodes.iter_mut().for_each(
+        |(style, is_active, states, mut active_timer, mut state_timers)| {
+            active_timer.tick(is_active, time.delta(), &style.timing(&style.active));
+
+            for (state, state_styles) in style.states.iter() {
+                let timer = match state_timers.iter().position(|(name, _)| name == state) {
+                    Some(index) => &mut state_timers.0[index].1,
+                    None => {
+                        state_timers
+                            .0
+                            .push((state.clone(), InteractionTimer::default()));
+                        &mut state_timers.0.last_mut().unwrap().1
+                    }
+                };
+
+                timer.tick(
+                    states.is_some_and(|states| states.contains(state)),
+                    time.delta(),
+                    &style.timing(state_styles),
+                );
+            }
+        },
+    );
+}
+
 #[derive(SystemParam)]
 pub struct UiStyleQuery<'w, 's> {
     pub server: Res<'w, AssetServer>,
@@ -214,6 +427,11 @@ impl<'w, 's> UiStyleQuery<'w, 's> {
         computed: &ComputedStyle,
         attr: &StyleAttr,
     ) -> Result<(), QueryEntityError> {
+        // text spans have no node
+        if self.apply_interpolated_text(entity, ratio, computed, attr) {
+            return Ok(());
+        }
+
         let mut style = self.node.get_mut(entity)?;
         match attr {
             StyleAttr::Display(display) => style.display = *display,
@@ -316,6 +534,53 @@ impl<'w, 's> UiStyleQuery<'w, 's> {
                     .get_mut(entity)
                     .map(|mut bg| bg.0 = lerp_color(&computed.background, color, ratio));
             }
+            StyleAttr::ShadowColor(color) => {
+                if let Some(computed_shadow) = computed.shadow.as_ref() {
+                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
+                        shadow[0].color = lerp_color(&computed_shadow[0].color, color, ratio)
+                    });
+                }
+            }
+            StyleAttr::ShadowOffset(x, y) => {
+                if let Some(computed_shadow) = computed.shadow.as_ref() {
+                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
+                        shadow[0].x_offset = lerp_val(&computed_shadow[0].x_offset, x, ratio);
+                        shadow[0].y_offset = lerp_val(&computed_shadow[0].y_offset, y, ratio);
+                    });
+                }
+            }
+            StyleAttr::ShadowBlur(blur) => {
+                if let Some(computed_shadow) = computed.shadow.as_ref() {
+                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
+                        shadow[0].blur_radius =
+                            lerp_val(&computed_shadow[0].blur_radius, blur, ratio);
+                    });
+                }
+            }
+            StyleAttr::ShadowSpread(spread) => {
+                if let Some(computed_shadow) = computed.shadow.as_ref() {
+                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
+                        shadow[0].spread_radius =
+                            lerp_val(&computed_shadow[0].spread_radius, spread, ratio);
+                    });
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// text related transitions, returns `false`
+    /// if the attribute is not a text style.
+    fn apply_interpolated_text(
+        &mut self,
+        entity: Entity,
+        ratio: f32,
+        computed: &ComputedStyle,
+        attr: &StyleAttr,
+    ) -> bool {
+        match attr {
             StyleAttr::FontColor(color) => {
                 _ = self.text_colors.get_mut(entity).map(|mut tc| {
                     **tc = lerp_color(&computed.font_color, color, ratio);
@@ -345,13 +610,6 @@ impl<'w, 's> UiStyleQuery<'w, 's> {
                     };
                 });
             }
-            StyleAttr::ShadowColor(color) => {
-                if let Some(computed_shadow) = computed.shadow.as_ref() {
-                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
-                        shadow[0].color = lerp_color(&computed_shadow[0].color, color, ratio)
-                    });
-                }
-            }
             StyleAttr::TextShadow(shadow) => {
                 if let Some(computed_shadow) = computed.text_shadow.as_ref() {
                     _ = self.text_shadows.get_mut(entity).map(|mut s| {
@@ -360,88 +618,191 @@ impl<'w, 's> UiStyleQuery<'w, 's> {
                     });
                 }
             }
-            StyleAttr::ShadowOffset(x, y) => {
-                if let Some(computed_shadow) = computed.shadow.as_ref() {
-                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
-                        shadow[0].x_offset = lerp_val(&computed_shadow[0].x_offset, x, ratio);
-                        shadow[0].y_offset = lerp_val(&computed_shadow[0].y_offset, y, ratio);
-                    });
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+fn continues_style_transition(
+    mut cmd: Commands,
+    mut transitions: Query<(Entity, &HtmlStyle, &mut StyleTransition)>,
+    time: Res<Time<Real>>,
+) {
+    transitions.iter_mut().for_each(|(entity, style, mut transition)| {
+        if transition.timer.is_finished() {
+            cmd.entity(entity).remove::<StyleTransition>();
+            return;
+        }
+        transition.timer.tick(true, time.delta(), &style.timing(&[]));
+    });
+}
+
+/// the inherited text properties
+#[derive(Clone)]
+struct TextCascade {
+    font: Option<FontReference>,
+    font_size: f32,
+    font_color: Color,
+}
+
+impl From<&ComputedStyle> for TextCascade {
+    fn from(style: &ComputedStyle) -> Self {
+        TextCascade {
+            font: style.font.clone(),
+            font_size: style.font_size,
+            font_color: style.font_color,
+        }
+    }
+}
+
+/// the changed styles, then all styles to cascade
+type CascadeQueries<'w, 's> = (
+    Query<'w, 's, Entity, Changed<HtmlStyle>>,
+    Query<'w, 's, (Entity, &'static mut HtmlStyle)>,
+);
+
+/// cascades `font`, `font_size` and `font_color` down
+/// the tree, to every node not setting them itself.
+/// Crosses custom components and slots, the
+/// roots start with the default style.
+///
+/// Only the subtrees of changed, added or moved
+/// nodes are cascaded again.
+fn cascade_text_styles(
+    mut styles: ParamSet<CascadeQueries>,
+    moved: Query<Entity, (Changed<ChildOf>, With<Node>)>,
+    mut unparented: RemovedComponents<ChildOf>,
+    parents: Query<&ChildOf>,
+    children: Query<&Children>,
+    server: Res<AssetServer>,
+) {
+    let dirty = styles
+        .p0()
+        .iter()
+        .chain(moved.iter())
+        .chain(unparented.read())
+        .collect::<HashSet<_>>();
+
+    if dirty.is_empty() {
+        return;
+    }
+
+    let mut styles = styles.p1();
+
+    // the closest styled ancestor passes on its computed text
+    // style, a dirty ancestor cascades the node anyway
+    let mut stack = dirty
+        .iter()
+        .filter(|entity| {
+            parents
+                .iter_ancestors(**entity)
+                .all(|ancestor| !dirty.contains(&ancestor))
+        })
+        .map(|entity| {
+            let cascade = parents
+                .iter_ancestors(*entity)
+                .find_map(|ancestor| styles.get(ancestor).ok())
+                .map(|(_, style)| TextCascade::from(&style.computed))
+                .unwrap_or_else(|| TextCascade::from(&ComputedStyle::default()));
+            (*entity, cascade)
+        })
+        .collect::<Vec<_>>();
+
+    while let Some((entity, mut cascade)) = stack.pop() {
+        // nodes without style pass it through
+        if let Ok((_, mut style)) = styles.get_mut(entity) {
+            let declared = style.declared;
+
+            if declared.font {
+                // resolve once, descendants share the handle
+                if let Some(FontReference::Path(path)) = style.computed.font.as_ref() {
+                    style.computed.font = Some(FontReference::Handle(server.load(path)));
                 }
+                cascade.font = style.computed.font.clone();
+            } else if !same_font(&style.computed.font, &cascade.font) {
+                style.computed.font = cascade.font.clone();
             }
-            StyleAttr::ShadowBlur(blur) => {
-                if let Some(computed_shadow) = computed.shadow.as_ref() {
-                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
-                        shadow[0].blur_radius =
-                            lerp_val(&computed_shadow[0].blur_radius, blur, ratio);
-                    });
-                }
+
+            if declared.font_size {
+                cascade.font_size = style.computed.font_size;
+            } else if style.computed.font_size != cascade.font_size {
+                style.computed.font_size = cascade.font_size;
             }
-            StyleAttr::ShadowSpread(spread) => {
-                if let Some(computed_shadow) = computed.shadow.as_ref() {
-                    _ = self.shadow.get_mut(entity).map(|mut shadow| {
-                        shadow[0].spread_radius =
-                            lerp_val(&computed_shadow[0].spread_radius, spread, ratio);
-                    });
-                }
+
+            if declared.font_color {
+                cascade.font_color = style.computed.font_color;
+            } else if style.computed.font_color != cascade.font_color {
+                style.computed.font_color = cascade.font_color;
             }
-            _ => (),
         }
 
-        Ok(())
+        if let Ok(children) = children.get(entity) {
+            stack.extend(children.iter().map(|child| (child, cascade.clone())));
+        }
     }
 }
 
-fn update_node_style(
-    mut nodes: Query<(Entity, &mut HtmlStyle, Has<UiActive>)>,
+fn same_font(a: &Option<FontReference>, b: &Option<FontReference>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(FontReference::Handle(a)), Some(FontReference::Handle(b))) => a == b,
+        (Some(FontReference::Path(a)), Some(FontReference::Path(b))) => a == b,
+        _ => false,
+    }
+}
+
+pub(crate) fn update_node_style(
+    mut nodes: Query<(Entity, &mut HtmlStyle, Option<&StyleTransition>)>,
     mut ui_style: UiStyleQuery,
     hover_timer: Query<&HoverTimer>,
     press_timer: Query<&PressedTimer>,
+    active_timer: Query<&ActiveTimer>,
+    state_timers: Query<&StateTimers>,
     server: Res<AssetServer>,
 ) {
-    for (entity, mut html_style, is_active) in nodes.iter_mut() {
-        ui_style.apply_computed(entity, &mut html_style.computed, &server);
+    for (entity, mut html_style, transition) in nodes.iter_mut() {
+        // only caches the font handle, not a change of the style
+        let computed = &mut html_style.bypass_change_detection().computed;
+        ui_style.apply_computed(entity, computed, &server);
+        let html_style = &*html_style;
 
-        let hover_ratio = hover_timer
-            .get(entity)
-            .map(|t| t.fraction())
-            .unwrap_or_default();
-
-        let hover_ratio = html_style
-            .computed
-            .easing
-            .map(|ease| EasingCurve::new(0., 1., ease).sample(hover_ratio))
-            .flatten()
-            .unwrap_or(hover_ratio);
+        if let Some(transition) = transition {
+            let timing = html_style.timing(&[]);
 
-        for hover_style in html_style.hover.iter() {
-            ui_style
-                .apply_interpolated(entity, hover_ratio, &html_style.computed, hover_style)
-                .expect("node has no style, impossible");
+            // spans have no node to transition
+            for style in transition.styles.iter() {
+                let ratio = transition.timer.ratio(style, &timing);
+                _ = ui_style.apply_interpolated(entity, ratio, &transition.from, style);
+            }
         }
 
-        let press_ratio = press_timer
-            .get(entity)
-            .map(|t| t.fraction())
-            .unwrap_or_default();
-
-        let press_ratio = html_style
-            .computed
-            .easing
-            .map(|ease| EasingCurve::new(0., 1., ease).sample(press_ratio))
-            .flatten()
-            .unwrap_or(press_ratio);
+        let state_timers = state_timers.get(entity).ok();
+        let builtin = [
+            (hover_timer.get(entity).ok().map(|t| &t.0), &html_style.hover),
+            (press_timer.get(entity).ok().map(|t| &t.0), &html_style.pressed),
+            (active_timer.get(entity).ok().map(|t| &t.0), &html_style.active),
+        ];
+        let custom = html_style.states.iter().map(|(state, styles)| {
+            (state_timers.and_then(|timers| timers.timer(state)), styles)
+        });
 
-        for press_style in html_style.pressed.iter() {
-            ui_style
-                .apply_interpolated(entity, press_ratio, &html_style.computed, press_style)
-                .expect("node has no style, impossible");
-        }
+        for (timer, styles) in builtin.into_iter().chain(custom) {
+            let Some(timer) = timer else {
+                continue;
+            };
+            let timing = html_style.timing(styles);
 
-        let active_ratio = is_active.then_some(1.).unwrap_or_default();
-        for active_style in html_style.active.iter() {
-            ui_style
-                .apply_interpolated(entity, active_ratio, &html_style.computed, active_style)
-                .expect("node has no style, impossible");
+            // inactive states do not override other
+            // states, spans only take text styles
+            for style in styles.iter() {
+                if timer.progress(style, &timing) <= 0. {
+                    continue;
+                }
+                let ratio = timer.ratio(style, &timing);
+                _ = ui_style.apply_interpolated(entity, ratio, &html_style.computed, style);
+            }
         }
     }
 }
@@ -451,6 +812,8 @@ fn update_node_style(
 pub struct PressedTimer(InteractionTimer);
 
 impl PressedTimer {
+    #[deprecated(note = "durations come from the style, use `PressedTimer::default`")]
+    #[allow(deprecated)]
     pub fn new(d: Duration) -> Self {
         Self(InteractionTimer::new(d))
     }
@@ -461,11 +824,38 @@ impl PressedTimer {
 pub struct HoverTimer(InteractionTimer);
 
 impl HoverTimer {
+    #[deprecated(note = "durations come from the style, use `HoverTimer::default`")]
+    #[allow(deprecated)]
     pub fn new(d: Duration) -> Self {
         Self(InteractionTimer::new(d))
     }
 }
 
+#[derive(Component, Default, Clone, Reflect, Deref, DerefMut)]
+#[reflect]
+pub struct ActiveTimer(InteractionTimer);
+
+impl ActiveTimer {
+    #[deprecated(note = "durations come from the style, use `ActiveTimer::default`")]
+    #[allow(deprecated)]
+    pub fn new(d: Duration) -> Self {
+        Self(InteractionTimer::new(d))
+    }
+}
+
+/// the timers of custom states, by prefix
+#[derive(Component, Default, Clone, Reflect, Deref)]
+#[reflect]
+pub struct StateTimers(Vec<(String, InteractionTimer)>);
+
+impl StateTimers {
+    pub fn timer(&self, state: &str) -> Option<&InteractionTimer> {
+        self.iter()
+            .find(|(name, _)| name == state)
+            .map(|(_, timer)| timer)
+    }
+}
+
 #[derive(Debug, Reflect, Clone)]
 #[reflect]
 pub struct ComputedStyle {
@@ -485,6 +875,12 @@ pub struct ComputedStyle {
     pub font_color: Color,
     pub atlas: Option<Atlas>,
     pub delay: f32,
+    /// defaults to `delay`
+    pub leave_delay: Option<f32>,
+    /// per property timing
+    pub transitions: Vec<PropertyTransition>,
+    /// transition the changes of dynamic styles
+    pub animate_changes: bool,
     pub duration: f32,
     pub iterations: i64,
     pub fps: i64,
@@ -516,6 +912,9 @@ impl Default for ComputedStyle {
             text_layout: None,
             atlas: None,
             delay: 0.,
+            leave_delay: None,
+            transitions: Vec::new(),
+            animate_changes: false,
             duration: 0.,
             fps: 1,
             frames: Vec::new(),
@@ -530,6 +929,106 @@ impl Default for ComputedStyle {
     }
 }
 
+/// the inline styles of a template node. Stylesheets
+/// and classes are applied below them.
+#[derive(Component, Default, Clone, Debug, Deref, Reflect)]
+#[reflect]
+pub struct NodeStyles(pub(crate) Vec<StyleAttr>);
+
+/// the last compiled dynamic styles of a node by expression
+/// index, the start of an `animate_changes` transition.
+#[derive(Component, Default, Clone, Debug, Deref, Reflect)]
+#[reflect]
+pub struct DynamicStyles(pub(crate) Vec<(usize, StyleAttr)>);
+
+/// The active classes of a template node. Changing the
+/// list recomputes the node style, transitioning
+/// with the `delay` and `ease` of the node.
+///
+/// ```ignore
+/// fn open(mut lists: Query<&mut UiClassList>) {
+///     lists.iter_mut().for_each(|mut list| list.toggle("open"));
+/// }
+/// ```
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect]
+pub struct UiClassList {
+    classes: Vec<String>,
+    /// the template defining the classes
+    template: AssetId<HtmlTemplate>,
+}
+
+impl UiClassList {
+    pub(crate) fn new(template: AssetId<HtmlTemplate>, classes: Vec<String>) -> Self {
+        Self { classes, template }
+    }
+
+    pub(crate) fn template(&self) -> AssetId<HtmlTemplate> {
+        self.template
+    }
+
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+
+    pub fn contains(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+
+    /// adds a class, it is applied after
+    /// the already active classes.
+    pub fn add(&mut self, class: impl Into<String>) {
+        let class = class.into();
+        if !self.contains(&class) {
+            self.classes.push(class);
+        }
+    }
+
+    pub fn remove(&mut self, class: &str) {
+        self.classes.retain(|c| c != class);
+    }
+
+    /// adds or removes a class, returns
+    /// `true` if the class is now active.
+    pub fn toggle(&mut self, class: &str) -> bool {
+        match self.contains(class) {
+            true => {
+                self.remove(class);
+                false
+            }
+            false => {
+                self.add(class);
+                true
+            }
+        }
+    }
+}
+
+/// interpolates a node from its previous style
+/// after its classes changed.
+#[derive(Component, Clone, Reflect)]
+#[reflect]
+pub struct StyleTransition {
+    pub from: ComputedStyle,
+    pub styles: Vec<StyleAttr>,
+    pub timer: InteractionTimer,
+}
+
+impl StyleTransition {
+    /// the interpolated style at the progress of the timer
+    pub fn current(&self, timing: &TransitionTiming) -> ComputedStyle {
+        let mut current = HtmlStyle {
+            computed: self.from.clone(),
+            ..default()
+        };
+        for style in self.styles.iter() {
+            let ratio = self.timer.ratio(style, timing);
+            current.add_style_attr(lerp_style(&self.from, style, ratio), None);
+        }
+        current.computed
+    }
+}
+
 /// this components holds all relevant style
 /// attributes.
 #[derive(Component, Default, Clone, Debug, Reflect)]
@@ -539,6 +1038,19 @@ pub struct HtmlStyle {
     pub hover: Vec<StyleAttr>,
     pub pressed: Vec<StyleAttr>,
     pub active: Vec<StyleAttr>,
+    /// styles of custom states, by prefix
+    pub states: Vec<(String, Vec<StyleAttr>)>,
+    pub declared: DeclaredText,
+}
+
+/// the inheritable text properties set on a node,
+/// the others cascade down from the closest ancestor.
+#[derive(Default, Clone, Copy, Debug, Reflect)]
+#[reflect]
+pub struct DeclaredText {
+    pub font: bool,
+    pub font_size: bool,
+    pub font_color: bool,
 }
 
 impl From<Vec<StyleAttr>> for HtmlStyle {
@@ -552,39 +1064,78 @@ impl From<Vec<StyleAttr>> for HtmlStyle {
 }
 
 impl HtmlStyle {
-    pub fn add_style_attr(&mut self, attr: StyleAttr, server: Option<&AssetServer>) {
-        match attr {
-            StyleAttr::Hover(style) => {
-                let style = *style;
-                match self
-                    .hover
-                    .iter()
-                    .position(|s| std::mem::discriminant(s) == std::mem::discriminant(&style))
-                {
-                    Some(index) => self.hover.insert(index, style),
-                    None => self.hover.push(style),
-                }
-            }
-            StyleAttr::Pressed(style) => {
-                let style = *style;
-                match self
-                    .pressed
-                    .iter()
-                    .position(|s| std::mem::discriminant(s) == std::mem::discriminant(&style))
-                {
-                    Some(index) => self.pressed.insert(index, style),
-                    None => self.pressed.push(style),
+    /// inserts the components, that depend on the computed
+    /// style, timers, shadows and outline. Existing timers
+    /// are kept, restyling a hovered node keeps it hovered.
+    pub(crate) fn insert_components(&self, mut cmd: EntityCommands) {
+        cmd.insert_if_new((
+            PressedTimer::default(),
+            HoverTimer::default(),
+            ActiveTimer::default(),
+            StateTimers::default(),
+        ));
+
+        if let Some(shadow) = self.computed.shadow.as_ref() {
+            cmd.insert(shadow.clone());
+        }
+
+        if let Some(shadow) = self.computed.text_shadow.as_ref() {
+            cmd.insert(*shadow);
+        }
+
+        if let Some(text_layout) = self.computed.text_layout.as_ref() {
+            cmd.insert(*text_layout);
+        }
+
+        if let Some(outline) = self.computed.outline.as_ref() {
+            cmd.insert(*outline);
+        }
+
+        #[cfg(feature = "picking")]
+        if let Some(pickable) = self.computed.pickable.as_ref() {
+            cmd.insert(pickable.clone());
+        }
+    }
+
+    /// the transition timing of a state, its own `delay`,
+    /// `leave_delay`, `ease` and `transition` take precedence.
+    /// `leave_delay` falls back to `delay`.
+    pub fn timing<'a>(&'a self, state_styles: &'a [StyleAttr]) -> TransitionTiming<'a> {
+        let mut timing = TransitionTiming {
+            enter: self.computed.delay,
+            leave: self.computed.leave_delay.unwrap_or(self.computed.delay),
+            easing: self.computed.easing,
+            node: &self.computed.transitions,
+            state: &[],
+        };
+
+        let mut leave = None;
+        for style in state_styles.iter() {
+            match style {
+                StyleAttr::Delay(delay) => {
+                    timing.enter = *delay;
+                    leave = leave.or(Some(*delay));
                 }
+                StyleAttr::LeaveDelay(delay) => leave = Some(*delay),
+                StyleAttr::Easing(easing) => timing.easing = Some(*easing),
+                StyleAttr::Transition(transitions) => timing.state = transitions,
+                _ => (),
             }
-            StyleAttr::Active(style) => {
-                let style = *style;
-                match self
-                    .active
-                    .iter()
-                    .position(|s| std::mem::discriminant(s) == std::mem::discriminant(&style))
-                {
-                    Some(index) => self.active.insert(index, style),
-                    None => self.active.push(style),
+        }
+
+        timing.leave = leave.unwrap_or(timing.leave);
+        timing
+    }
+
+    pub fn add_style_attr(&mut self, attr: StyleAttr, server: Option<&AssetServer>) {
+        match attr {
+            StyleAttr::Hover(style) => replace_style(&mut self.hover, *style),
+            StyleAttr::Pressed(style) => replace_style(&mut self.pressed, *style),
+            StyleAttr::Active(style) => replace_style(&mut self.active, *style),
+            StyleAttr::Prefixed(prefix, style) => {
+                match self.states.iter_mut().find(|(state, _)| *state == prefix) {
+                    Some((_, styles)) => replace_style(styles, *style),
+                    None => self.states.push((prefix, vec![*style])),
                 }
             }
             StyleAttr::Display(display) => self.computed.node.display = display,
@@ -644,12 +1195,21 @@ impl HtmlStyle {
             StyleAttr::GridColumn(grid_placement) => {
                 self.computed.node.grid_column = grid_placement
             }
-            StyleAttr::FontSize(f) => self.computed.font_size = f,
-            StyleAttr::FontColor(color) => self.computed.font_color = color,
+            StyleAttr::FontSize(f) => {
+                self.computed.font_size = f;
+                self.declared.font_size = true;
+            }
+            StyleAttr::FontColor(color) => {
+                self.computed.font_color = color;
+                self.declared.font_color = true;
+            }
             StyleAttr::TextLayout(text_layout) => self.computed.text_layout = Some(text_layout),
             StyleAttr::Background(color) => self.computed.background = color,
             StyleAttr::Atlas(f) => self.computed.atlas = f,
             StyleAttr::Delay(f) => self.computed.delay = f,
+            StyleAttr::LeaveDelay(f) => self.computed.leave_delay = Some(f),
+            StyleAttr::Transition(transitions) => self.computed.transitions = transitions,
+            StyleAttr::AnimateChanges(animate) => self.computed.animate_changes = animate,
             StyleAttr::Duration(f) => self.computed.duration = f,
             StyleAttr::FPS(f) => self.computed.fps = f,
             StyleAttr::Iterations(f) => self.computed.iterations = f,
@@ -720,6 +1280,7 @@ impl HtmlStyle {
                 })
             }
             StyleAttr::Font(font) => {
+                self.declared.font = true;
                 self.computed.font = match (font, server) {
                     // opportunistically load the font if the asset server is available
                     (FontReference::Path(path), Some(server)) => {
@@ -734,6 +1295,60 @@ impl HtmlStyle {
     }
 }
 
+/// replaces a style of the same kind, or adds it
+pub(crate) fn replace_style(styles: &mut Vec<StyleAttr>, style: StyleAttr) {
+    match styles
+        .iter()
+        .position(|s| std::mem::discriminant(s) == std::mem::discriminant(&style))
+    {
+        Some(index) => styles[index] = style,
+        None => styles.push(style),
+    }
+}
+
+/// the style between a computed style and a target, like
+/// `apply_interpolated`. Other styles are taken as they are.
+fn lerp_style(from: &ComputedStyle, attr: &StyleAttr, ratio: f32) -> StyleAttr {
+    let node = &from.node;
+    match attr {
+        StyleAttr::Left(val) => StyleAttr::Left(lerp_val(&node.left, val, ratio)),
+        StyleAttr::Right(val) => StyleAttr::Right(lerp_val(&node.right, val, ratio)),
+        StyleAttr::Top(val) => StyleAttr::Top(lerp_val(&node.top, val, ratio)),
+        StyleAttr::Bottom(val) => StyleAttr::Bottom(lerp_val(&node.bottom, val, ratio)),
+        StyleAttr::Width(val) => StyleAttr::Width(lerp_val(&node.width, val, ratio)),
+        StyleAttr::Height(val) => StyleAttr::Height(lerp_val(&node.height, val, ratio)),
+        StyleAttr::MinWidth(val) => StyleAttr::MinWidth(lerp_val(&node.min_width, val, ratio)),
+        StyleAttr::MinHeight(val) => StyleAttr::MinHeight(lerp_val(&node.min_height, val, ratio)),
+        StyleAttr::MaxWidth(val) => StyleAttr::MaxWidth(lerp_val(&node.max_width, val, ratio)),
+        StyleAttr::MaxHeight(val) => StyleAttr::MaxHeight(lerp_val(&node.max_height, val, ratio)),
+        StyleAttr::Margin(rect) => StyleAttr::Margin(lerp_rect(&node.margin, rect, ratio)),
+        StyleAttr::Padding(rect) => StyleAttr::Padding(lerp_rect(&node.padding, rect, ratio)),
+        StyleAttr::Border(rect) => StyleAttr::Border(lerp_rect(&node.border, rect, ratio)),
+        StyleAttr::BorderRadius(rect) => {
+            StyleAttr::BorderRadius(lerp_rect(&from.border_radius, rect, ratio))
+        }
+        StyleAttr::BorderColor(color) => {
+            StyleAttr::BorderColor(lerp_color(&from.border_color, color, ratio))
+        }
+        StyleAttr::FlexGrow(g) => StyleAttr::FlexGrow(node.flex_grow.lerp(*g, ratio)),
+        StyleAttr::FlexShrink(s) => StyleAttr::FlexShrink(node.flex_shrink.lerp(*s, ratio)),
+        StyleAttr::FlexBasis(val) => StyleAttr::FlexBasis(lerp_val(&node.flex_basis, val, ratio)),
+        StyleAttr::RowGap(val) => StyleAttr::RowGap(lerp_val(&node.row_gap, val, ratio)),
+        StyleAttr::ColumnGap(val) => StyleAttr::ColumnGap(lerp_val(&node.column_gap, val, ratio)),
+        StyleAttr::Background(color) => {
+            StyleAttr::Background(lerp_color(&from.background, color, ratio))
+        }
+        StyleAttr::ImageColor(color) => {
+            StyleAttr::ImageColor(lerp_color(&from.image_color, color, ratio))
+        }
+        StyleAttr::FontColor(color) => {
+            StyleAttr::FontColor(lerp_color(&from.font_color, color, ratio))
+        }
+        StyleAttr::FontSize(size) => StyleAttr::FontSize(from.font_size.lerp(*size, ratio)),
+        style => style.clone(),
+    }
+}
+
 fn lerp_color(start: &Color, end: &Color, ratio: f32) -> Color {
     let lin = start
         .to_linear()
@@ -761,3 +1376,370 @@ fn lerp_val(start: &Val, end: &Val, ratio: f32) -> Val {
         _ => *start,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_app;
+    use test_case::test_case;
+
+    fn cascade_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            bevy::app::TaskPoolPlugin::default(),
+            bevy::asset::AssetPlugin::default(),
+        ));
+        app.add_systems(Update, cascade_text_styles);
+        app
+    }
+
+    fn font_color(app: &App, entity: Entity) -> Color {
+        app.world()
+            .get::<HtmlStyle>(entity)
+            .unwrap()
+            .computed
+            .font_color
+    }
+
+    #[test]
+    fn test_cascade_changed_subtrees() {
+        let red = Color::srgb(1., 0., 0.);
+        let blue = Color::srgb(0., 0., 1.);
+        let green = Color::srgb(0., 1., 0.);
+
+        let mut app = cascade_app();
+        let parent = app
+            .world_mut()
+            .spawn((Node::default(), HtmlStyle::from(vec![StyleAttr::FontColor(red)])))
+            .id();
+        let child = app
+            .world_mut()
+            .spawn((Node::default(), HtmlStyle::default(), ChildOf(parent)))
+            .id();
+        app.update();
+        assert_eq!(font_color(&app, child), red);
+
+        // unchanged nodes are not cascaded again
+        app.world_mut()
+            .get_mut::<HtmlStyle>(child)
+            .unwrap()
+            .bypass_change_detection()
+            .computed
+            .font_color = green;
+        app.update();
+        assert_eq!(font_color(&app, child), green);
+
+        // a changed ancestor cascades its subtree
+        let mut style = app.world_mut().get_mut::<HtmlStyle>(parent).unwrap();
+        style.computed.font_color = blue;
+        app.update();
+        assert_eq!(font_color(&app, child), blue);
+
+        // moved nodes take the style of their new ancestors
+        let other = app
+            .world_mut()
+            .spawn((Node::default(), HtmlStyle::from(vec![StyleAttr::FontColor(green)])))
+            .id();
+        app.update();
+        app.world_mut().entity_mut(child).insert(ChildOf(other));
+        app.update();
+        assert_eq!(font_color(&app, child), green);
+    }
+
+    #[derive(Component)]
+    struct Selected;
+
+    #[test]
+    fn test_register_state_rejects_breakpoints() {
+        let mut app = App::new();
+        app.insert_resource(HuiBreakpoints::default().with("tablet", 900.));
+        app.register_hui_state::<Selected>("selected")
+            .register_hui_state::<Selected>("md")
+            .register_hui_state::<Selected>("tablet")
+            .register_hui_state::<Selected>("portrait")
+            .register_hui_state::<Selected>("hover");
+
+        let states = app.world().resource::<RegisteredStates>();
+        assert_eq!(states.as_slice(), ["selected"]);
+    }
+
+    #[test]
+    fn test_breakpoints_inserted_after_state() {
+        let mut app = test_app();
+        app.register_hui_state::<Selected>("selected")
+            .register_hui_state::<Selected>("tablet");
+        app.insert_resource(HuiBreakpoints::default().with("tablet", 900.));
+        app.update();
+
+        let states = app.world().resource::<RegisteredStates>();
+        assert_eq!(states.as_slice(), ["selected"]);
+
+        let node = app.world_mut().spawn(Selected).id();
+        app.update();
+        let states = app.world().get::<UiStates>(node).unwrap();
+        assert_eq!(states.as_slice(), ["selected"]);
+    }
+
+    fn transition(
+        property: &str,
+        duration: f32,
+        easing: Option<EaseFunction>,
+    ) -> PropertyTransition {
+        PropertyTransition {
+            property: property.into(),
+            duration,
+            easing,
+        }
+    }
+
+    #[test_case(0., 100, 0.2, true => 0.5; "enter")]
+    #[test_case(0.5, 500, 0.2, true => 1.; "enter clamped")]
+    #[test_case(1., 100, 0.4, false => 0.75; "leave")]
+    #[test_case(0.2, 500, 0.4, false => 0.; "leave clamped")]
+    #[test_case(0., 16, 0., true => 1.; "zero duration enter")]
+    #[test_case(1., 16, 0., false => 0.; "zero duration leave")]
+    fn test_step(progress: f32, delta_ms: u64, duration: f32, forward: bool) -> f32 {
+        step(progress, Duration::from_millis(delta_ms), duration, forward)
+    }
+
+    #[test_case(&[(true, 100)] => 0.5; "enter duration")]
+    #[test_case(&[(true, 200), (false, 100)] => 0.75; "leave duration")]
+    #[test_case(&[(true, 100), (false, 400)] => 0.; "left")]
+    fn test_tick(ticks: &[(bool, u64)]) -> f32 {
+        let timing = TransitionTiming {
+            enter: 0.2,
+            leave: 0.4,
+            easing: None,
+            node: &[],
+            state: &[],
+        };
+
+        let mut timer = InteractionTimer::default();
+        for (active, delta_ms) in ticks {
+            timer.tick(*active, Duration::from_millis(*delta_ms), &timing);
+        }
+        timer.fraction()
+    }
+
+    #[test_case(StyleAttr::Width(Val::Px(10.)) => 0.5; "shared duration")]
+    #[test_case(StyleAttr::Background(Color::WHITE) => 0.25; "property duration")]
+    #[test_case(StyleAttr::FontColor(Color::WHITE) => 1.; "zero property duration")]
+    fn test_progress(attr: StyleAttr) -> f32 {
+        let node = [
+            transition("background", 0.4, None),
+            transition("font_color", 0., None),
+        ];
+        let timing = TransitionTiming {
+            enter: 0.2,
+            leave: 0.2,
+            easing: None,
+            node: &node,
+            state: &[],
+        };
+
+        let mut timer = InteractionTimer::default();
+        timer.tick(true, Duration::from_millis(100), &timing);
+        timer.progress(&attr, &timing)
+    }
+
+    #[test_case(StyleAttr::Width(Val::Px(10.)) => 0.25; "node easing")]
+    #[test_case(StyleAttr::Background(Color::WHITE) => 0.0625; "falls back to node easing")]
+    #[test_case(StyleAttr::FontColor(Color::WHITE) => 0.5; "property easing")]
+    fn test_ratio(attr: StyleAttr) -> f32 {
+        let node = [
+            transition("background", 0.4, None),
+            transition("font_color", 0.2, Some(EaseFunction::Linear)),
+        ];
+        let timing = TransitionTiming {
+            enter: 0.2,
+            leave: 0.2,
+            easing: Some(EaseFunction::QuadraticIn),
+            node: &node,
+            state: &[],
+        };
+
+        let mut timer = InteractionTimer::default();
+        timer.tick(true, Duration::from_millis(100), &timing);
+        timer.ratio(&attr, &timing)
+    }
+
+    #[test_case("background" => Some(0.1); "state overrides node")]
+    #[test_case("width" => Some(0.3); "node")]
+    #[test_case("height" => Some(0.5); "state")]
+    #[test_case("border" => None; "none")]
+    fn test_transitions(property: &str) -> Option<f32> {
+        let node = [
+            transition("background", 0.4, None),
+            transition("width", 0.3, None),
+        ];
+        let state = [
+            transition("background", 0.1, None),
+            transition("height", 0.5, None),
+        ];
+        let timing = TransitionTiming {
+            enter: 0.,
+            leave: 0.,
+            easing: None,
+            node: &node,
+            state: &state,
+        };
+
+        assert_eq!(timing.transitions().count(), 3);
+        timing.property(property).map(|t| t.duration)
+    }
+
+    #[test_case(None, vec![] => (0.2, 0.2); "leave falls back to delay")]
+    #[test_case(Some(0.5), vec![] => (0.2, 0.5); "node leave delay")]
+    #[test_case(Some(0.5), vec![StyleAttr::Delay(0.1)] => (0.1, 0.1); "state delay")]
+    #[test_case(None, vec![StyleAttr::Delay(0.1), StyleAttr::LeaveDelay(0.3)] => (0.1, 0.3); "state leave delay")]
+    #[test_case(None, vec![StyleAttr::LeaveDelay(0.3), StyleAttr::Delay(0.1)] => (0.1, 0.3); "state leave delay first")]
+    #[test_case(Some(0.5), vec![StyleAttr::Delay(0.)] => (0., 0.); "zero state delay")]
+    fn test_timing(leave_delay: Option<f32>, state: Vec<StyleAttr>) -> (f32, f32) {
+        let mut style = HtmlStyle::default();
+        style.computed.delay = 0.2;
+        style.computed.leave_delay = leave_delay;
+
+        let timing = style.timing(&state);
+        (timing.enter, timing.leave)
+    }
+
+    /// runs the state timers and styles with a manual clock
+    struct StateApp {
+        app: App,
+        now: std::time::Instant,
+        node: Entity,
+    }
+
+    impl StateApp {
+        fn new(styles: Vec<StyleAttr>) -> Self {
+            let mut app = App::new();
+            app.add_plugins((
+                bevy::app::TaskPoolPlugin::default(),
+                bevy::asset::AssetPlugin::default(),
+            ));
+            let now = std::time::Instant::now();
+            let mut time = Time::<Real>::new(now);
+            time.update_with_instant(now);
+            app.insert_resource(time);
+            app.add_systems(
+                Update,
+                (continues_state_checking, update_node_style).chain(),
+            );
+
+            let node = app
+                .world_mut()
+                .spawn((
+                    Node::default(),
+                    HtmlStyle::from(styles),
+                    ActiveTimer::default(),
+                    StateTimers::default(),
+                ))
+                .id();
+            Self { app, now, node }
+        }
+
+        fn tick(&mut self, seconds: f32) -> &mut Self {
+            self.now += Duration::from_secs_f32(seconds);
+            self.app
+                .world_mut()
+                .resource_mut::<Time<Real>>()
+                .update_with_instant(self.now);
+            self.app.update();
+            self
+        }
+
+        fn set_states(&mut self, states: &[&str]) -> &mut Self {
+            let states = UiStates(states.iter().map(|state| state.to_string()).collect());
+            self.app.world_mut().entity_mut(self.node).insert(states);
+            self
+        }
+
+        fn node(&self) -> &Node {
+            self.app.world().get::<Node>(self.node).unwrap()
+        }
+
+        fn state_progress(&self, state: &str) -> f32 {
+            self.app
+                .world()
+                .get::<StateTimers>(self.node)
+                .and_then(|timers| timers.timer(state))
+                .map(|timer| timer.fraction())
+                .unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn test_active_state_timer() {
+        let mut state = StateApp::new(vec![
+            StyleAttr::Delay(1.),
+            StyleAttr::LeaveDelay(2.),
+            StyleAttr::Width(Val::Px(0.)),
+            StyleAttr::Active(Box::new(StyleAttr::Width(Val::Px(100.)))),
+        ]);
+
+        state.tick(0.5);
+        assert_eq!(state.node().width, Val::Px(0.));
+
+        // forward with the delay
+        let node = state.node;
+        state.app.world_mut().entity_mut(node).insert(UiActive);
+        state.tick(0.5);
+        let timer = state.app.world().get::<ActiveTimer>(node).unwrap();
+        assert_eq!(timer.fraction(), 0.5);
+        assert_eq!(state.node().width, Val::Px(50.));
+
+        // backward with the leave delay
+        state.app.world_mut().entity_mut(node).remove::<UiActive>();
+        state.tick(0.5);
+        let timer = state.app.world().get::<ActiveTimer>(node).unwrap();
+        assert_eq!(timer.fraction(), 0.25);
+        assert_eq!(state.node().width, Val::Px(25.));
+
+        state.tick(1.);
+        assert_eq!(state.node().width, Val::Px(0.));
+    }
+
+    #[test]
+    fn test_custom_state_timers() {
+        let mut state = StateApp::new(vec![
+            StyleAttr::Delay(1.),
+            StyleAttr::Width(Val::Px(0.)),
+            StyleAttr::Height(Val::Px(0.)),
+            StyleAttr::Prefixed("open".into(), Box::new(StyleAttr::Width(Val::Px(100.)))),
+            StyleAttr::Prefixed("open".into(), Box::new(StyleAttr::Height(Val::Px(100.)))),
+            StyleAttr::Prefixed("wide".into(), Box::new(StyleAttr::Width(Val::Px(200.)))),
+        ]);
+
+        state.set_states(&["open"]).tick(0.5);
+        assert_eq!(state.state_progress("open"), 0.5);
+        assert_eq!(state.state_progress("wide"), 0.);
+        assert_eq!(state.node().width, Val::Px(50.));
+        assert_eq!(state.node().height, Val::Px(50.));
+
+        // the later state wins the shared property
+        state.set_states(&["open", "wide"]).tick(0.25);
+        assert_eq!(state.state_progress("open"), 0.75);
+        assert_eq!(state.state_progress("wide"), 0.25);
+        assert_eq!(state.node().width, Val::Px(50.));
+        assert_eq!(state.node().height, Val::Px(75.));
+
+        state.set_states(&["open"]).tick(0.25);
+        assert_eq!(state.state_progress("open"), 1.);
+        assert_eq!(state.state_progress("wide"), 0.);
+        assert_eq!(state.node().height, Val::Px(100.));
+    }
+
+    #[test]
+    fn test_inactive_state_is_skipped() {
+        let mut state = StateApp::new(vec![
+            StyleAttr::Width(Val::Px(0.)),
+            StyleAttr::Prefixed("open".into(), Box::new(StyleAttr::Width(Val::Px(100.)))),
+            StyleAttr::Prefixed("wide".into(), Box::new(StyleAttr::Width(Val::Px(200.)))),
+        ]);
+
+        // `wide` has no progress and keeps the width of `open`
+        state.set_states(&["open"]).tick(0.1);
+        assert_eq!(state.state_progress("wide"), 0.);
+        assert_eq!(state.node().width, Val::Px(100.));
+    }
+}
@@ -0,0 +1,520 @@
+use crate::{
+    adaptor::LoadContextAdaptor,
+    compile::CompileNodeEvent,
+    data::{HtmlTemplate, StyleAttr},
+    error::ParseError,
+    media::UiMedia,
+    parse::parse_stylesheet,
+    styles::{HtmlStyle, InteractionTimer, NodeStyles, StyleTransition, UiClassList},
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader},
+    platform::collections::HashSet,
+    prelude::*,
+};
+
+pub struct StylesheetPlugin;
+impl Plugin for StylesheetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<HuiStylesheet>();
+        app.init_asset_loader::<HuiStylesheetLoader>();
+        app.init_resource::<HuiStylesheets>();
+        app.register_type::<UiSelector>();
+        app.add_systems(
+            Update,
+            restyle_nodes
+                .after(crate::build::move_children_to_slot)
+                .before(crate::styles::update_node_style),
+        );
+    }
+}
+
+/// Registers global stylesheets, applied to
+/// every node of every template.
+///
+/// `app.add_plugins(HuiStylesheetPlugin::new(&["styles/main.hui.css"]))`
+pub struct HuiStylesheetPlugin {
+    paths: Vec<&'static str>,
+}
+
+impl HuiStylesheetPlugin {
+    /// Paths start at your assets folder. Later stylesheets
+    /// win over earlier ones with the same specificity.
+    pub fn new(paths: &[&'static str]) -> Self {
+        Self {
+            paths: paths.to_vec(),
+        }
+    }
+}
+
+impl Plugin for HuiStylesheetPlugin {
+    fn build(&self, app: &mut App) {
+        // loaded on startup, the `AssetPlugin`
+        // may be added after this plugin
+        let paths = self.paths.clone();
+        app.init_resource::<HuiStylesheets>();
+        app.add_systems(
+            PreStartup,
+            move |server: Res<AssetServer>, mut stylesheets: ResMut<HuiStylesheets>| {
+                stylesheets.extend(paths.iter().map(|path| server.load(*path)));
+            },
+        );
+    }
+}
+
+/// The active global stylesheets, in order.
+/// Changing the list restyles every node.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct HuiStylesheets(pub Vec<Handle<HuiStylesheet>>);
+
+/// A parsed `.hui.css` stylesheet.
+#[derive(Asset, Debug, Default, Reflect)]
+#[reflect]
+pub struct HuiStylesheet {
+    pub rules: Vec<StyleRule>,
+}
+
+/// the styles of a single selector.
+#[derive(Debug, Clone, Reflect)]
+#[reflect]
+pub struct StyleRule {
+    pub selector: Selector,
+    pub styles: Vec<StyleAttr>,
+}
+
+/// compound selectors from left to right,
+/// the last one matches the styled node.
+#[derive(Debug, Clone, Default, PartialEq, Reflect)]
+#[reflect]
+pub struct Selector(pub Vec<SelectorPart>);
+
+#[derive(Debug, Clone, PartialEq, Reflect)]
+#[reflect]
+pub struct SelectorPart {
+    /// relation to the part before
+    pub combinator: Combinator,
+    pub compound: CompoundSelector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect]
+pub enum Combinator {
+    /// `node text`
+    Descendant,
+    /// `node > text`
+    Child,
+}
+
+/// `button#start.primary`
+#[derive(Debug, Clone, Default, PartialEq, Reflect)]
+#[reflect]
+pub struct CompoundSelector {
+    pub node_type: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+}
+
+/// what stylesheet selectors match against, together
+/// with the `UiClassList`. Every build template node has one.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect]
+pub struct UiSelector {
+    pub node_type: String,
+    pub id: Option<String>,
+}
+
+impl CompoundSelector {
+    fn matches(&self, node: &UiSelector, classes: &UiClassList) -> bool {
+        self.node_type
+            .as_ref()
+            .is_none_or(|node_type| *node_type == node.node_type)
+            && self.id.as_ref().is_none_or(|id| node.id.as_ref() == Some(id))
+            && self.classes.iter().all(|class| classes.contains(class))
+    }
+}
+
+/// the selector, classes and closest
+/// template node ancestor of a node.
+pub type SelectorNode<'a> = (&'a UiSelector, &'a UiClassList, Option<Entity>);
+
+impl Selector {
+    /// `(ids, classes, node types)`, higher wins.
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        self.0.iter().fold((0, 0, 0), |(ids, classes, types), part| {
+            (
+                ids + part.compound.id.iter().count(),
+                classes + part.compound.classes.len(),
+                types + part.compound.node_type.iter().count(),
+            )
+        })
+    }
+
+    /// `selector_of` returns the `SelectorNode` of a node.
+    pub fn matches<'a>(
+        &self,
+        entity: Entity,
+        selector_of: &impl Fn(Entity) -> Option<SelectorNode<'a>>,
+    ) -> bool {
+        Self::matches_parts(&self.0, entity, selector_of)
+    }
+
+    fn matches_parts<'a>(
+        parts: &[SelectorPart],
+        entity: Entity,
+        selector_of: &impl Fn(Entity) -> Option<SelectorNode<'a>>,
+    ) -> bool {
+        let Some((last, rest)) = parts.split_last() else {
+            return true;
+        };
+
+        let Some((node, classes, mut parent)) = selector_of(entity) else {
+            return false;
+        };
+
+        if !last.compound.matches(node, classes) {
+            return false;
+        }
+
+        if rest.is_empty() {
+            return true;
+        }
+
+        match last.combinator {
+            Combinator::Child => {
+                parent.is_some_and(|parent| Self::matches_parts(rest, parent, selector_of))
+            }
+            Combinator::Descendant => {
+                while let Some(ancestor) = parent {
+                    if Self::matches_parts(rest, ancestor, selector_of) {
+                        return true;
+                    }
+                    parent = selector_of(ancestor).and_then(|(_, _, parent)| parent);
+                }
+                false
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HuiStylesheetLoader;
+impl AssetLoader for HuiStylesheetLoader {
+    type Asset = HuiStylesheet;
+    type Settings = ();
+    type Error = ParseError;
+
+    async fn load<'a>(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut bevy::asset::LoadContext<'a>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|err| ParseError::FailedToRead(err.to_string()))?;
+        let mut adapter = LoadContextAdaptor { load_context };
+        match parse_stylesheet::<crate::error::VerboseHtmlError>(&bytes, &mut adapter) {
+            Ok((_, rules)) => Ok(HuiStylesheet { rules }),
+            Err(err) => match err {
+                nom::Err::Incomplete(_) => Err(ParseError::Incomplete),
+                nom::Err::Error(err) | nom::Err::Failure(err) => {
+                    let file_path = load_context.path().to_str().unwrap_or_default();
+                    Err(ParseError::Nom(err.format(&bytes, file_path)))
+                }
+            },
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hui.css"]
+    }
+}
+
+type MovedOrAdded = (
+    With<UiSelector>,
+    Or<(Added<UiSelector>, Changed<ChildOf>)>,
+);
+
+/// computes the style of new, moved, reclassed and resized
+/// nodes, or of every node, when a stylesheet changed.
+/// Stylesheet rules, then classes, then inline styles.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn restyle_nodes(
+    mut cmd: Commands,
+    mut events: MessageReader<AssetEvent<HuiStylesheet>>,
+    stylesheets: Res<HuiStylesheets>,
+    sheets: Res<Assets<HuiStylesheet>>,
+    templates: Res<Assets<HtmlTemplate>>,
+    mut nodes: Query<(&NodeStyles, &UiClassList, Option<&UiMedia>, &mut HtmlStyle)>,
+    changed: Query<Entity, MovedOrAdded>,
+    added: Query<(), Added<UiSelector>>,
+    reclassed: Query<(Entity, Ref<UiClassList>), Changed<UiClassList>>,
+    resized: Query<Entity, Changed<UiMedia>>,
+    all: Query<Entity, With<UiSelector>>,
+    selectors: Query<(&UiSelector, &UiClassList)>,
+    parents: Query<&ChildOf>,
+    children: Query<&Children>,
+) {
+    let reload = stylesheets.is_changed()
+        | events.read().fold(false, |reload, ev| {
+            let id = match ev {
+                AssetEvent::Added { id }
+                | AssetEvent::Modified { id }
+                | AssetEvent::Removed { id } => id,
+                _ => return reload,
+            };
+            reload | stylesheets.iter().any(|sheet| sheet.id() == *id)
+        });
+
+    let rules = stylesheets
+        .iter()
+        .filter_map(|handle| sheets.get(handle))
+        .flat_map(|sheet| sheet.rules.iter())
+        .collect::<Vec<_>>();
+
+    // new nodes are build with their classes
+    let reclassed = reclassed
+        .iter()
+        .filter_map(|(entity, list)| (!list.is_added()).then_some(entity))
+        .collect::<HashSet<_>>();
+
+    // crossed a breakpoint
+    let resized = resized.iter().collect::<HashSet<_>>();
+
+    let targets = match reload {
+        true => all.iter().collect::<HashSet<_>>(),
+        // nothing to match, nodes are build
+        // with their own styles
+        false if rules.is_empty() => reclassed.union(&resized).copied().collect(),
+        // descendant selectors may match
+        // below a changed node
+        false => changed
+            .iter()
+            .chain(reclassed.iter().copied())
+            .chain(resized.iter().copied())
+            .flat_map(|entity| std::iter::once(entity).chain(children.iter_descendants(entity)))
+            .collect::<HashSet<_>>(),
+    };
+
+    // the closest template node ancestor
+    let selector_of = |entity: Entity| {
+        let (node, classes) = selectors.get(entity).ok()?;
+        let parent = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| selectors.contains(*ancestor));
+        Some((node, classes, parent))
+    };
+
+    for entity in targets {
+        let Ok((node_styles, class_list, media, mut html_style)) = nodes.get_mut(entity) else {
+            continue;
+        };
+
+        let mut matched = rules
+            .iter()
+            .filter(|rule| rule.selector.matches(entity, &selector_of))
+            .collect::<Vec<_>>();
+
+        // new nodes are build with their own styles, any other
+        // target may lose a rule of an ancestor or its old parent
+        let is_reclassed = reclassed.contains(&entity);
+        if matched.is_empty() && !reload && added.contains(entity) {
+            continue;
+        }
+
+        // stable, declaration order for equal specificity
+        matched.sort_by_key(|rule| rule.selector.specificity());
+
+        let class_styles = templates
+            .get(class_list.template())
+            .map(|template| template.class_styles(class_list.classes()))
+            .unwrap_or_default();
+
+        let styles = matched
+            .iter()
+            .flat_map(|rule| rule.styles.iter())
+            .chain(class_styles.iter())
+            .chain(node_styles.iter())
+            .cloned();
+        let styles = match media {
+            Some(media) => media.resolve_all(styles),
+            None => styles.collect::<Vec<_>>(),
+        };
+
+        let previous = std::mem::replace(&mut *html_style, HtmlStyle::from(styles.clone()));
+        html_style.insert_components(cmd.entity(entity));
+
+        let timed = html_style.computed.delay > 0. || !html_style.computed.transitions.is_empty();
+        if is_reclassed && timed {
+            cmd.entity(entity).insert(StyleTransition {
+                from: previous.computed,
+                styles,
+                timer: InteractionTimer::default(),
+            });
+        }
+
+        // dynamic attributes are applied on top
+        cmd.trigger(CompileNodeEvent { entity });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptor::AssetLoadAdaptor;
+    use crate::prelude::TemplateProperties;
+    use crate::testing::{find, spawn_template, test_app};
+    use bevy::platform::collections::HashMap;
+    use test_case::test_case;
+
+    struct DummyLoaderAdapter;
+    impl AssetLoadAdaptor for DummyLoaderAdapter {
+        fn load<'a, A: bevy::asset::Asset>(
+            &mut self,
+            _path: impl Into<bevy::asset::AssetPath<'a>>,
+        ) -> bevy::asset::Handle<A> {
+            bevy::asset::Handle::default()
+        }
+    }
+
+    fn selector(input: &str) -> Selector {
+        let source = format!("{input} {{ width: 1px; }}");
+        let (_, mut rules) = parse_stylesheet::<nom::error::VerboseError<_>>(
+            source.as_bytes(),
+            &mut DummyLoaderAdapter,
+        )
+        .unwrap();
+        rules.remove(0).selector
+    }
+
+    fn node(node_type: &str, id: Option<&str>, classes: &[&str]) -> (UiSelector, UiClassList) {
+        (
+            UiSelector {
+                node_type: node_type.to_string(),
+                id: id.map(|id| id.to_string()),
+            },
+            UiClassList::new(
+                AssetId::default(),
+                classes.iter().map(|class| class.to_string()).collect(),
+            ),
+        )
+    }
+
+    // node.card > node.body > text#title.large
+    #[test_case("text" => true; "node type")]
+    #[test_case("#title" => true; "id")]
+    #[test_case("text.large#title" => true; "compound")]
+    #[test_case("text.small" => false; "missing class")]
+    #[test_case("node.body > text" => true; "child")]
+    #[test_case("node.card > text" => false; "not a child")]
+    #[test_case("node.card text" => true; "descendant")]
+    #[test_case(".card .body #title" => true; "ancestry")]
+    #[test_case(".body .card text" => false; "wrong order")]
+    #[test_case("* > * > *" => true; "universal")]
+    fn test_selector_matches(input: &str) -> bool {
+        test_matches(input, None)
+    }
+
+    #[test_case("text.open" => true; "added class")]
+    #[test_case(".card .open" => true; "added class ancestry")]
+    #[test_case("text.large" => false; "removed class")]
+    fn test_selector_matches_toggled(input: &str) -> bool {
+        test_matches(input, Some("large"))
+    }
+
+    fn test_matches(input: &str, toggle: Option<&str>) -> bool {
+        let card = Entity::from_raw_u32(1).unwrap();
+        let body = Entity::from_raw_u32(2).unwrap();
+        let title = Entity::from_raw_u32(3).unwrap();
+
+        let mut tree = HashMap::from([
+            (card, (node("node", None, &["card"]), None)),
+            (body, (node("node", None, &["body"]), Some(card))),
+            (title, (node("text", Some("title"), &["large"]), Some(body))),
+        ]);
+
+        if let Some(class) = toggle {
+            let (_, classes) = &mut tree.get_mut(&title).unwrap().0;
+            classes.toggle(class);
+            classes.toggle("open");
+        }
+
+        selector(input).matches(title, &|entity| {
+            tree.get(&entity)
+                .map(|((node, classes), parent)| (node, classes, *parent))
+        })
+    }
+
+    /// a node inside `.open`, styled by the stylesheet
+    fn open_panel() -> (App, Entity, Entity) {
+        let mut app = test_app();
+        let (_, rules) = parse_stylesheet::<nom::error::VerboseError<_>>(
+            b".open text { width: 10px; }",
+            &mut DummyLoaderAdapter,
+        )
+        .unwrap();
+        let sheet = app
+            .world_mut()
+            .resource_mut::<Assets<HuiStylesheet>>()
+            .add(HuiStylesheet { rules });
+        app.world_mut().resource_mut::<HuiStylesheets>().push(sheet);
+
+        spawn_template(
+            &mut app,
+            r#"
+            <template>
+                <node>
+                    <node id="panel" class="open">
+                        <text id="label">label</text>
+                    </node>
+                    <node id="other"/>
+                </node>
+            </template>
+            "#,
+            TemplateProperties::default(),
+        );
+
+        let panel = find(&mut app, "panel")[0];
+        let label = find(&mut app, "label")[0];
+        assert_eq!(app.world().get::<Node>(label).unwrap().width, Val::Px(10.));
+        (app, panel, label)
+    }
+
+    #[test]
+    fn test_restyle_removed_ancestor_class() {
+        let (mut app, panel, label) = open_panel();
+        app.world_mut()
+            .get_mut::<UiClassList>(panel)
+            .unwrap()
+            .remove("open");
+        app.update();
+
+        assert_eq!(app.world().get::<Node>(label).unwrap().width, Val::Auto);
+    }
+
+    #[test]
+    fn test_restyle_reparented() {
+        let (mut app, _, label) = open_panel();
+        let other = find(&mut app, "other")[0];
+        app.world_mut().entity_mut(other).add_child(label);
+        app.update();
+
+        assert_eq!(app.world().get::<Node>(label).unwrap().width, Val::Auto);
+    }
+
+    #[test]
+    fn test_plugin_before_asset_plugin() {
+        let mut app = App::new();
+        app.add_plugins(HuiStylesheetPlugin::new(&["main.hui.css"]));
+        app.add_plugins((
+            bevy::app::TaskPoolPlugin::default(),
+            bevy::asset::AssetPlugin::default(),
+        ));
+        app.init_asset::<HuiStylesheet>();
+        app.update();
+
+        assert_eq!(app.world().resource::<HuiStylesheets>().len(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::build::TemplateProperties;
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// theme tokens are available as `theme.<name>`
+/// properties in every template.
+pub(crate) const THEME_PREFIX: &str = "theme.";
+
+pub struct ThemePlugin;
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HuiTheme>();
+        app.register_type::<HuiTheme>();
+        app.add_systems(Update, apply_theme);
+    }
+}
+
+/// Named values usable in any template, without
+/// declaring a `<property>`. `background="$primary"`
+/// is short for `background="{theme.primary}"`.
+///
+/// Changing the theme recompiles every template.
+///
+/// ```ignore
+/// app.insert_resource(
+///     HuiTheme::default()
+///         .with("primary", "#3A7BD5")
+///         .with("spacing_md", "12px"),
+/// );
+/// ```
+#[derive(Resource, Debug, Clone, Default, Reflect, Deref, DerefMut)]
+#[reflect]
+pub struct HuiTheme(pub HashMap<String, String>);
+
+impl HuiTheme {
+    pub fn with(mut self, key: &str, value: &str) -> Self {
+        self.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> &mut Self {
+        self.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// replaces all theme properties with the current tokens.
+    pub(crate) fn apply_to(&self, properties: &mut TemplateProperties) {
+        properties.retain(|key, _| !key.starts_with(THEME_PREFIX));
+        properties.extend(
+            self.iter()
+                .map(|(key, value)| (format!("{THEME_PREFIX}{key}"), value.as_str().into())),
+        );
+    }
+}
+
+/// new templates get the theme on spawn, existing ones
+/// are recompiled by their changed properties.
+pub(crate) fn apply_theme(
+    theme: Res<HuiTheme>,
+    mut properties: Query<&mut TemplateProperties>,
+) {
+    if !theme.is_changed() || theme.is_added() {
+        return;
+    }
+
+    properties
+        .iter_mut()
+        .for_each(|mut properties| theme.apply_to(&mut properties));
+}
@@ -0,0 +1,217 @@
+use crate::data::{Attribute, PropertyKind, StyleAttr};
+use bevy::prelude::*;
+
+/// a single template property. Strings are parsed
+/// when compiled, typed values are used as they are.
+///
+/// ```ignore
+/// props
+///     .set_typed("bar_color", Color::srgb(1., 0., 0.))
+///     .set_typed("hp", 0.75)
+///     .set_typed("width", Val::Percent(75.));
+/// ```
+#[derive(Debug, Clone, PartialEq, Reflect)]
+#[reflect]
+pub enum PropertyValue {
+    Str(String),
+    Number(f32),
+    Bool(bool),
+    Color(Color),
+    Val(Val),
+    List(Vec<PropertyValue>),
+    Entity(Entity),
+}
+
+impl Default for PropertyValue {
+    fn default() -> Self {
+        PropertyValue::Str(String::new())
+    }
+}
+
+impl PropertyValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// numbers and strings that are numbers
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            PropertyValue::Number(n) => Some(*n),
+            PropertyValue::Str(s) => s.trim().parse::<f32>().ok(),
+            _ => None,
+        }
+    }
+
+    /// if the value is or can be parsed as the declared `type`.
+    pub fn is_kind(&self, kind: PropertyKind) -> bool {
+        match (kind, self) {
+            (PropertyKind::Any | PropertyKind::String, _) => true,
+            (PropertyKind::Float, value) => value.as_number().is_some(),
+            (PropertyKind::Int, value) => value.as_number().is_some_and(|n| n.fract() == 0.),
+            (PropertyKind::Bool, PropertyValue::Bool(_)) => true,
+            (PropertyKind::Bool, PropertyValue::Str(s)) => matches!(s.trim(), "true" | "false"),
+            (PropertyKind::Color, PropertyValue::Color(_)) => true,
+            (PropertyKind::Color, PropertyValue::Str(s)) => {
+                crate::parse::parse_color::<nom::error::Error<&[u8]>>(s.as_bytes())
+                    .is_ok_and(|(rest, _)| rest.is_empty())
+            }
+            (PropertyKind::Val, PropertyValue::Val(_)) => true,
+            (PropertyKind::Val, PropertyValue::Str(s)) => {
+                crate::parse::parse_val::<nom::error::Error<&[u8]>>(s.as_bytes())
+                    .is_ok_and(|(rest, _)| rest.is_empty())
+            }
+            (PropertyKind::List, PropertyValue::List(_) | PropertyValue::Str(_)) => true,
+            (PropertyKind::Entity, PropertyValue::Entity(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// the elements of a list, strings are
+    /// split like `for` lists `[a, b, c]`.
+    pub fn items(&self) -> Vec<PropertyValue> {
+        match self {
+            PropertyValue::List(items) => items.clone(),
+            other => crate::compile::split_list(&other.to_string())
+                .into_iter()
+                .map(PropertyValue::Str)
+                .collect(),
+        }
+    }
+
+    /// the attribute of a typed value, without parsing.
+    /// `None` for strings and styles of another type.
+    pub(crate) fn to_attribute(&self, prefix: Option<&str>, ident: &str) -> Option<Attribute> {
+        let style = match (self, ident) {
+            (PropertyValue::Color(color), _) => match ident {
+                "background" => StyleAttr::Background(*color),
+                "border_color" => StyleAttr::BorderColor(*color),
+                "font_color" => StyleAttr::FontColor(*color),
+                "image_color" => StyleAttr::ImageColor(*color),
+                "shadow_color" => StyleAttr::ShadowColor(*color),
+                _ => return None,
+            },
+            (PropertyValue::Val(val), _) => match ident {
+                "left" => StyleAttr::Left(*val),
+                "right" => StyleAttr::Right(*val),
+                "top" => StyleAttr::Top(*val),
+                "bottom" => StyleAttr::Bottom(*val),
+                "width" => StyleAttr::Width(*val),
+                "height" => StyleAttr::Height(*val),
+                "min_width" => StyleAttr::MinWidth(*val),
+                "min_height" => StyleAttr::MinHeight(*val),
+                "max_width" => StyleAttr::MaxWidth(*val),
+                "max_height" => StyleAttr::MaxHeight(*val),
+                "flex_basis" => StyleAttr::FlexBasis(*val),
+                "row_gap" => StyleAttr::RowGap(*val),
+                "column_gap" => StyleAttr::ColumnGap(*val),
+                "shadow_blur" => StyleAttr::ShadowBlur(*val),
+                "shadow_spread" => StyleAttr::ShadowSpread(*val),
+                "padding" => StyleAttr::Padding(UiRect::all(*val)),
+                "margin" => StyleAttr::Margin(UiRect::all(*val)),
+                "border" => StyleAttr::Border(UiRect::all(*val)),
+                "border_radius" => StyleAttr::BorderRadius(UiRect::all(*val)),
+                _ => return None,
+            },
+            (PropertyValue::Number(n), "font_size") => StyleAttr::FontSize(*n),
+            (PropertyValue::Number(n), "aspect_ratio") => StyleAttr::AspectRatio(*n),
+            (PropertyValue::Number(n), "flex_grow") => StyleAttr::FlexGrow(*n),
+            (PropertyValue::Number(n), "flex_shrink") => StyleAttr::FlexShrink(*n),
+            (PropertyValue::Bool(b), "animate_changes") => StyleAttr::AnimateChanges(*b),
+            _ => return None,
+        };
+
+        Some(Attribute::Style(crate::parse::prefixed_style(
+            prefix.map(|prefix| prefix.as_bytes()),
+            style,
+        )))
+    }
+}
+
+/// the string the value is parsed from
+/// in mixed attributes and text content.
+impl std::fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropertyValue::Str(s) => write!(f, "{s}"),
+            PropertyValue::Number(n) => write!(f, "{n}"),
+            PropertyValue::Bool(b) => write!(f, "{b}"),
+            PropertyValue::Color(color) => write!(f, "{}", color.to_srgba().to_hex()),
+            PropertyValue::Val(val) => match val {
+                Val::Auto => write!(f, "auto"),
+                Val::Px(n) => write!(f, "{n}px"),
+                Val::Percent(n) => write!(f, "{n}%"),
+                Val::Vw(n) => write!(f, "{n}vw"),
+                Val::Vh(n) => write!(f, "{n}vh"),
+                Val::VMin(n) => write!(f, "{n}vmin"),
+                Val::VMax(n) => write!(f, "{n}vmax"),
+            },
+            PropertyValue::List(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                Ok(())
+            }
+            PropertyValue::Entity(entity) => write!(f, "{entity}"),
+        }
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        PropertyValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        PropertyValue::Str(value)
+    }
+}
+
+impl From<f32> for PropertyValue {
+    fn from(value: f32) -> Self {
+        PropertyValue::Number(value)
+    }
+}
+
+impl From<i32> for PropertyValue {
+    fn from(value: i32) -> Self {
+        PropertyValue::Number(value as f32)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        PropertyValue::Bool(value)
+    }
+}
+
+impl From<Color> for PropertyValue {
+    fn from(value: Color) -> Self {
+        PropertyValue::Color(value)
+    }
+}
+
+impl From<Val> for PropertyValue {
+    fn from(value: Val) -> Self {
+        PropertyValue::Val(value)
+    }
+}
+
+impl From<Entity> for PropertyValue {
+    fn from(value: Entity) -> Self {
+        PropertyValue::Entity(value)
+    }
+}
+
+impl<T: Into<PropertyValue>> From<Vec<T>> for PropertyValue {
+    fn from(value: Vec<T>) -> Self {
+        PropertyValue::List(value.into_iter().map(Into::into).collect())
+    }
+}
@@ -0,0 +1,93 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implements `HuiProps`, every field is written as
+/// a template property with the name of the field.
+///
+/// - `#[hui(rename = "title")]` uses another property name
+/// - `#[hui(skip)]` leaves the field out
+///
+/// Fields that are `HuiProps` structs themselves are
+/// written as `field.name` keys.
+#[proc_macro_derive(HuiProps, attributes(hui))]
+pub fn derive_hui_props(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) if matches!(data.fields, Fields::Unnamed(_)) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`HuiProps` needs named fields",
+            ))
+        }
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`HuiProps` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut writes = Vec::new();
+    for field in fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+
+        let mut name = ident.to_string();
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("hui")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown `hui` attribute, expected `rename` or `skip`"))
+                }
+            })?;
+        }
+
+        if skip {
+            continue;
+        }
+
+        writes.push(quote! {
+            ::bevy_hui::prelude::HuiProps::write_props(
+                &self.#ident,
+                &if key.is_empty() {
+                    #name.to_string()
+                } else {
+                    format!("{}.{}", key, #name)
+                },
+                props,
+            );
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::bevy_hui::prelude::HuiProps for #ident #ty_generics #where_clause {
+            fn write_props(
+                &self,
+                key: &str,
+                props: &mut ::bevy_hui::prelude::TemplateProperties,
+            ) {
+                #(#writes)*
+            }
+        }
+    })
+}
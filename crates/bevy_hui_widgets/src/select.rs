@@ -6,6 +6,9 @@ use bevy_hui::prelude::*;
 /// A select is a button with 2 children. The current
 /// selected node and a hidden node, holding the options.
 ///
+/// The option list is opened by adding the `open` class
+/// and closed by removing it.
+///
 pub struct HuiSelectWidgetPlugin;
 impl Plugin for HuiSelectWidgetPlugin {
     fn build(&self, app: &mut App) {
@@ -71,16 +74,16 @@ fn init_select(
 
 fn open_list(
     selects: Query<(&Interaction, &UiTarget), (With<SelectInput>, Changed<Interaction>)>,
-    mut styles: Query<&mut HtmlStyle>,
+    mut class_lists: Query<&mut UiClassList>,
 ) {
     for (interaction, target) in selects.iter() {
-        let Ok(mut list_style) = styles.get_mut(**target) else {
+        let Ok(mut list_classes) = class_lists.get_mut(**target) else {
             continue;
         };
 
         match interaction {
             Interaction::Pressed => {
-                list_style.computed.node.display = Display::Grid;
+                list_classes.add("open");
             }
             _ => (),
         }
@@ -90,7 +93,7 @@ fn open_list(
 fn selection(
     mut events: MessageWriter<SelectionChangedEvent>,
     options: Query<(Entity, &ChildOf, &Interaction, &SelectOption), Changed<Interaction>>,
-    mut styles: Query<&mut HtmlStyle>,
+    mut class_lists: Query<&mut UiClassList>,
 ) {
     for (entity, parent, interaction, option) in options.iter() {
         if !matches!(interaction, Interaction::Pressed) {
@@ -103,8 +106,8 @@ fn selection(
         });
 
         // close the list
-        _ = styles.get_mut(parent.parent()).map(|mut style| {
-            style.computed.node.display = Display::None;
+        _ = class_lists.get_mut(parent.parent()).map(|mut classes| {
+            classes.remove("open");
         });
     }
 }
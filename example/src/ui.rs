@@ -73,11 +73,11 @@ fn setup(
 
     html_comps.register("aseprite", server.load("demo/aseprite.html"));
 
-    // a function that updates a property and triggers a recompile
+    // a function that updates a property, the
+    // template recompiles on its own
     html_funcs.register(
         "debug",
         |In(entity),
-         mut cmd: Commands,
          mut template_props: Query<&mut TemplateProperties>,
          scopes: Query<&TemplateScope>| {
             let Ok(scope) = scopes.get(entity) else {
@@ -89,8 +89,7 @@ fn setup(
             };
 
             let rng = rand::random::<u32>();
-            props.insert("title".to_string(), format!("{}", rng));
-            cmd.trigger(CompileContextEvent { entity: **scope });
+            props.set("title", &rng.to_string());
         },
     );
 }